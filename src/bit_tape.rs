@@ -0,0 +1,264 @@
+use std::fmt::{self, Display};
+use std::ops::Range;
+use crate::tape::{Alphabet, Direction, SimpleTape};
+
+/// Number of bits every cell is packed into
+const CELL_BITS: usize = 2;
+const CELLS_PER_WORD: usize = u64::BITS as usize / CELL_BITS;
+const CELL_MASK: u64 = (1 << CELL_BITS) - 1;
+
+/// Codes of the values a cell can contain
+const BLANK: u64 = 0;
+const FALSE: u64 = 1;
+const TRUE: u64 = 2;
+
+/// Tape for binary alphabets, which packs 32 cells into every machine word.
+/// Every cell contains the blank, Some(false) or Some(true), so it can be used
+/// in place of a Tape for any machine working on bools. The blank is None,
+/// unless the alphabet of the tape declares another one.
+#[derive(Clone)]
+pub struct BitTape {
+    /// Tape positions from 0 to infinity
+    positive_words: Vec<u64>,
+    /// Tape positions from -1 to -infinity
+    negative_words: Vec<u64>,
+    head_position: isize,
+    /// The positions that have been written or loaded
+    extent: Range<isize>,
+    alphabet: Option<Alphabet<bool>>
+}
+
+impl BitTape {
+    /// Create a new, empty tape
+    pub fn new() -> BitTape {
+        BitTape {
+            positive_words: Vec::new(),
+            negative_words: Vec::new(),
+            head_position: 0,
+            extent: 0..0,
+            alphabet: None
+        }
+    }
+
+    /// Create a tape from the values, with the head on the first one
+    pub fn tape(tape: Vec<Option<bool>>) -> BitTape {
+        let mut res = BitTape::new();
+        res.load(&tape);
+        res
+    }
+
+    fn blank(&self) -> Option<bool> {
+        self.alphabet.as_ref().and_then(|a| a.blank())
+    }
+
+    // The side of the tape the position is on, the index of the word in it and
+    // the shift of the cell in the word
+    fn locate(position: isize) -> (bool, usize, usize) {
+        let (negative, index) = if position >= 0 { (false, position as usize) } else { (true, position.unsigned_abs() - 1) };
+        (negative, index / CELLS_PER_WORD, index % CELLS_PER_WORD * CELL_BITS)
+    }
+
+    fn read_at(&self, position: isize) -> Option<bool> {
+        let (negative, word, shift) = BitTape::locate(position);
+        let words = if negative { &self.negative_words } else { &self.positive_words };
+        match words.get(word).map_or(BLANK, |w| w >> shift & CELL_MASK) {
+            FALSE => Some(false),
+            TRUE => Some(true),
+            _ => self.blank()
+        }
+    }
+
+    fn write_at(&mut self, position: isize, val: Option<bool>) {
+        // Blanks are stored as such, whatever symbol the alphabet uses for them
+        let code = match val {
+            _ if val == self.blank() => BLANK,
+            Some(false) => FALSE,
+            Some(true) => TRUE,
+            None => BLANK
+        };
+        self.extend(position);
+
+        let (negative, word, shift) = BitTape::locate(position);
+        let words = if negative { &mut self.negative_words } else { &mut self.positive_words };
+        if word >= words.len() {
+            if code == BLANK {
+                return;
+            }
+            words.resize(word + 1, 0);
+        }
+        words[word] = words[word] & !(CELL_MASK << shift) | code << shift;
+    }
+
+    fn extend(&mut self, position: isize) {
+        if self.extent.is_empty() {
+            self.extent = position..position + 1;
+        }
+        else {
+            self.extent.start = self.extent.start.min(position);
+            self.extent.end = self.extent.end.max(position + 1);
+        }
+    }
+}
+
+impl Default for BitTape {
+    fn default() -> BitTape {
+        BitTape::new()
+    }
+}
+
+impl SimpleTape<bool> for BitTape {
+    fn mv(&mut self, direction: Direction) -> Option<bool> {
+        self.head_position += direction as isize;
+        self.read()
+    }
+
+    fn mv_left(&mut self) -> Option<bool> {
+        self.mv(Direction::Left)
+    }
+
+    fn mv_right(&mut self) -> Option<bool> {
+        self.mv(Direction::Right)
+    }
+
+    fn read(&self) -> Option<bool> {
+        self.read_at(self.head_position)
+    }
+
+    fn write(&mut self, val: Option<bool>) {
+        self.write_at(self.head_position, val);
+    }
+
+    fn cell(&self, position: isize) -> Option<bool> {
+        self.read_at(position)
+    }
+
+    fn head_position(&self) -> isize {
+        self.head_position
+    }
+
+    fn extent(&self) -> Range<isize> {
+        self.extent.clone()
+    }
+
+    fn load(&mut self, input: &[Option<bool>]) {
+        self.positive_words.clear();
+        self.negative_words.clear();
+        self.head_position = 0;
+        self.extent = 0..0;
+        for (i, &v) in input.iter().enumerate() {
+            self.write_at(i as isize, v);
+        }
+    }
+
+    fn alphabet(&self) -> Option<&Alphabet<bool>> {
+        self.alphabet.as_ref()
+    }
+
+    fn set_alphabet(&mut self, alphabet: Option<Alphabet<bool>>) {
+        self.alphabet = alphabet;
+    }
+
+    fn boxed_clone(&self) -> Box<dyn SimpleTape<bool>> {
+        Box::new(self.clone())
+    }
+
+    fn contents(&self) -> Vec<Option<bool>> {
+        self.extent.clone().map(|i| self.read_at(i)).collect()
+    }
+
+    fn contents_trim_blanks(&self) -> Vec<Option<bool>> {
+        let blank = self.blank();
+        let start = self.extent.clone().find(|&i| self.read_at(i) != blank);
+        let end = self.extent.clone().rev().find(|&i| self.read_at(i) != blank);
+        match (start, end) {
+            (Some(start), Some(end)) => (start..=end).map(|i| self.read_at(i)).collect(),
+            _ => Vec::new()
+        }
+    }
+}
+
+/// Prints the cells as ones and zeros and blanks as underscores, e.g. [ 01_1 ]
+impl Display for BitTape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "[ ")?;
+        for i in self.extent.clone() {
+            match self.read_at(i) {
+                Some(bit) => write!(f, "{}", bit as u8)?,
+                None => write!(f, "_")?
+            }
+        }
+        write!(f, " ]")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::turing_machine::{Halting, RunOutcome, TuringMachine, Verdict};
+
+    #[test]
+    fn test_bits() {
+        let mut tape = BitTape::tape(vec![Some(true), None, Some(false)]);
+        assert_eq!(tape.mv_left(), None);
+        for _ in 0..200 {
+            tape.mv_left();
+        }
+        tape.write(Some(true));
+        assert_eq!(tape.extent(), -201..3);
+        assert_eq!(tape.cell(-201), Some(true));
+        assert_eq!(tape.cell(1), None);
+        assert_eq!(tape.cell(2), Some(false));
+
+        tape.write(None);
+        assert_eq!(tape.contents_trim_blanks(), vec![Some(true), None, Some(false)]);
+        assert_eq!(BitTape::tape(vec![Some(true), None, Some(false)]).to_string(), "[ 1_0 ]");
+
+        // Empty cells read as the blank of the alphabet
+        tape.set_alphabet(Some(Alphabet::new(Vec::new(), [true], Some(false))));
+        assert_eq!(tape.read(), Some(false));
+        assert_eq!(tape.contents_trim_blanks(), vec![Some(true)]);
+        tape.set_alphabet(Some(Alphabet::new([true], [], None)));
+        assert_eq!(tape.read(), None);
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        // Cells on both sides of the boundaries between the words of each half
+        let positions = [-65, -64, -33, -32, -1, 0, 31, 32, 33, 63, 64];
+        let mut tape = BitTape::new();
+        for (i, &position) in positions.iter().enumerate() {
+            while tape.head_position() > position { tape.mv_left(); }
+            while tape.head_position() < position { tape.mv_right(); }
+            tape.write(Some(i % 2 == 0));
+        }
+        for (i, &position) in positions.iter().enumerate() {
+            assert_eq!(tape.cell(position), Some(i % 2 == 0), "cell {}", position);
+        }
+        assert_eq!(tape.extent(), -65..65);
+        assert_eq!(tape.cell(-31), None);
+        assert_eq!(tape.cell(30), None);
+        assert_eq!(tape.cell(34), None);
+
+        // Clearing the last cell of a word leaves the first one of the next
+        tape.write(None);
+        assert_eq!(tape.cell(64), None);
+        assert_eq!(tape.cell(63), Some(false));
+    }
+
+    #[test]
+    fn test_false_blank() {
+        // The two state busy beaver with Some(false) as the blank
+        let mut tm = TuringMachine::new(Box::new(BitTape::new()));
+        tm.add_transition((0, Some(false)), (1, Some(true), Direction::Right));
+        tm.add_transition((0, Some(true)), (1, Some(true), Direction::Left));
+        tm.add_transition((1, Some(false)), (0, Some(true), Direction::Left));
+        tm.add_transition((1, Some(true)), (2, Some(true), Direction::Right));
+        tm.add_halting_state(2);
+        assert_eq!(tm.set_alphabet(Alphabet::new(Vec::new(), [true], Some(false))), Ok(()));
+
+        assert_eq!(tm.run(100, None).outcome, RunOutcome::Halted(Verdict::Halt));
+        assert_eq!(tm.steps(), 6);
+        assert_eq!(tm.tape().contents_trim_blanks(), vec![Some(true); 4]);
+        assert_eq!(tm.tape().cell(-3), Some(false));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::observer::TransitionEvent;
+use crate::turing_machine::State;
+
+/// A condition on which a bounded run of a turing machine should be
+/// interrupted. It is checked after every transition, conditions can be
+/// combined with All, Any and Not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint<V, S = State> {
+    /// Break when the machine enters the state from a different one.
+    EnterState(S),
+    /// Break after the symbol has been read.
+    ReadSymbol(Option<V>),
+    /// Break when the head arrives at the position.
+    HeadAt(isize),
+    /// Watchpoint that breaks when the contents of the cell at the position
+    /// are changed. Overwriting a symbol with itself does not count.
+    CellChanged(isize),
+    /// Break only if all of the conditions are met by the same transition.
+    All(Vec<Breakpoint<V, S>>),
+    /// Break if any of the conditions is met.
+    Any(Vec<Breakpoint<V, S>>),
+    /// Break if the condition is not met.
+    Not(Box<Breakpoint<V, S>>)
+}
+
+impl<V: PartialEq, S: PartialEq> Breakpoint<V, S> {
+    /// Check if the breakpoint fires for the transition that has just been
+    /// performed.
+    pub fn matches(&self, event: &TransitionEvent<V, S>) -> bool {
+        match self {
+            Breakpoint::EnterState(s) => event.to_state == *s && event.from_state != *s,
+            Breakpoint::ReadSymbol(a) => event.read == *a,
+            Breakpoint::HeadAt(p) => event.new_head_position == *p,
+            Breakpoint::CellChanged(p) => event.head_position == *p && event.read != event.written,
+            Breakpoint::All(conditions) => conditions.iter().all(|c| c.matches(event)),
+            Breakpoint::Any(conditions) => conditions.iter().any(|c| c.matches(event)),
+            Breakpoint::Not(condition) => !condition.matches(event)
+        }
+    }
+
+    /// Combine with another condition, so that both have to be met.
+    pub fn and(self, other: Breakpoint<V, S>) -> Breakpoint<V, S> {
+        Breakpoint::All(vec![self, other])
+    }
+
+    /// Combine with another condition, so that either one has to be met.
+    pub fn or(self, other: Breakpoint<V, S>) -> Breakpoint<V, S> {
+        Breakpoint::Any(vec![self, other])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::{Direction, Tape};
+    use crate::turing_machine::{Halting, RunOutcome, Transitionable, TuringMachine};
+
+    #[test]
+    fn test_breakpoints() {
+        // Walks right over the input and writes a marker behind it
+        let mut tm = TuringMachine::new(Box::new(Tape::tape(vec![Some('a'), Some('b'), Some('a')])));
+        tm.add_transition((0, Some('a')), (0, Some('a'), Direction::Right));
+        tm.add_transition((0, Some('b')), (0, Some('b'), Direction::Right));
+        tm.add_transition((0, None), (1, Some('#'), Direction::Left));
+        tm.add_transition((1, Some('a')), (1, Some('a'), Direction::Left));
+        tm.add_transition((1, Some('b')), (1, Some('b'), Direction::Left));
+        tm.add_transition((1, None), (2, None, Direction::Right));
+        tm.add_halting_state(2);
+
+        // Reading the first a on the way back, which moves the head off the input
+        let read_a_going_left = tm.add_breakpoint(Breakpoint::ReadSymbol(Some('a')).and(Breakpoint::HeadAt(-1)));
+        let marker = tm.add_breakpoint(Breakpoint::CellChanged(3));
+
+        let report = tm.run(100, None);
+        assert_eq!(report.outcome, RunOutcome::Breakpoint(marker));
+        assert_eq!(report.steps, 4);
+
+        let report = tm.run(100, None);
+        assert_eq!(report.outcome, RunOutcome::Breakpoint(read_a_going_left));
+        assert_eq!(tm.steps(), 7);
+
+        tm.clear_breakpoints();
+        tm.reset();
+        tm.add_breakpoint(Breakpoint::EnterState(1).or(Breakpoint::Not(Box::new(Breakpoint::ReadSymbol(Some('a'))))));
+        let report = tm.run(100, None);
+        assert_eq!(report.outcome, RunOutcome::Breakpoint(0));
+        assert_eq!(tm.steps(), 2);
+    }
+}
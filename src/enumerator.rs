@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+use crate::tape::{Direction, Tapeable};
+use crate::turing_machine::{State, Stateable, StepOutcome, Transitionable, TuringMachine};
+
+/// Turing machine that enumerates a language instead of deciding it. Some of
+/// its transitions print the contents of the work tape after they have been
+/// performed, and everything printed is yielded by the enumerator when it is
+/// used as an iterator.
+pub struct Enumerator<V: Tapeable, S: Stateable = State> {
+    machine: TuringMachine<V, S>,
+    /// The causes of all transitions that print the tape
+    printing: HashSet<(S, Option<V>)>,
+    /// Maximum number of steps the machine may perform since its last reset
+    max_steps: usize
+}
+
+impl<V: Tapeable, S: Stateable> Enumerator<V, S> {
+    /// Create an enumerator from the machine, which stops enumerating after
+    /// the machine has performed max_steps transitions in total.
+    pub fn new(machine: TuringMachine<V, S>, max_steps: usize) -> Enumerator<V, S> {
+        Enumerator {
+            machine,
+            printing: HashSet::new(),
+            max_steps
+        }
+    }
+
+    /// Add a transition, that prints the contents of the tape after it has
+    /// been performed. If there was already a transition registered to the
+    /// cause, it will be replaced and returned.
+    pub fn add_printing_transition(&mut self, cause: (S, Option<V>), effect: (S, Option<V>, Direction)) -> Option<(S, Option<V>, Direction)> {
+        self.printing.insert(cause.clone());
+        self.machine.add_transition(cause, effect)
+    }
+
+    /// Add a transition, that does not print anything. If there was already a
+    /// transition registered to the cause, it will be replaced and returned.
+    pub fn add_transition(&mut self, cause: (S, Option<V>), effect: (S, Option<V>, Direction)) -> Option<(S, Option<V>, Direction)> {
+        self.printing.remove(&cause);
+        self.machine.add_transition(cause, effect)
+    }
+
+    /// Restart the enumeration from the beginning.
+    pub fn reset(&mut self) {
+        self.machine.reset();
+    }
+
+    /// The machine doing the enumeration.
+    pub fn machine(&mut self) -> &mut TuringMachine<V, S> {
+        &mut self.machine
+    }
+}
+
+/// Yields the tape contents, without leading and trailing blanks, every time a
+/// printing transition has been performed. Ends when the machine halts, gets
+/// stuck or runs out of steps.
+impl<V: Tapeable, S: Stateable> Iterator for Enumerator<V, S> {
+    type Item = Vec<Option<V>>;
+
+    fn next(&mut self) -> Option<Vec<Option<V>>> {
+        while self.machine.steps() < self.max_steps && self.machine.verdict().is_none() {
+            let cause = (self.machine.current_state().clone(), self.machine.tape().read());
+            let steps = self.machine.steps();
+            let outcome = self.machine.step();
+
+            // The machine may also halt without performing a transition, e.g.
+            // on a self loop, which must not print anything
+            if self.machine.steps() > steps && self.printing.contains(&cause) {
+                return Some(self.machine.tape().contents_trim_blanks());
+            }
+            if outcome != StepOutcome::Continued {
+                return None;
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::Tape;
+    use crate::turing_machine::{Halting, HaltingPolicy};
+
+    #[test]
+    fn test_enumerate() {
+        // Prints a, aa, aaa and so on forever
+        let mut e = Enumerator::new(TuringMachine::new(Box::new(Tape::new())), 100);
+        e.add_printing_transition((0, None), (0, Some('a'), Direction::Right));
+        let words: Vec<String> = e.by_ref().take(3)
+            .map(|w| w.into_iter().flatten().collect())
+            .collect();
+        assert_eq!(words, vec!["a", "aa", "aaa"]);
+        assert_eq!(e.count(), 97);
+
+        // Prints ab, then halts
+        let mut e = Enumerator::new(TuringMachine::new(Box::new(Tape::new())), 100);
+        e.add_transition((0, None), (1, Some('a'), Direction::Right));
+        e.add_printing_transition((1, None), (2, Some('b'), Direction::Hold));
+        e.machine().add_halting_state(2);
+        assert_eq!(e.collect::<Vec<_>>(), vec![vec![Some('a'), Some('b')]]);
+
+        // A self loop halts the machine, without performing the transition
+        let mut e = Enumerator::new(TuringMachine::new(Box::new(Tape::tape(vec![Some('a')]))), 100);
+        e.add_printing_transition((0, Some('a')), (0, Some('a'), Direction::Hold));
+        e.machine().set_halting_policy(HaltingPolicy::SelfLoop);
+        assert_eq!(e.take(5).count(), 0);
+    }
+}
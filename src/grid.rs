@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::ops::Range;
+use crate::tape::Tapeable;
+use crate::turing_machine::{FinalStates, Halting, RunOutcome, State, Stateable, StepOutcome, TransitionTable, Verdict};
+
+/// Directions the head can be moved in on a two dimensional tape. Up moves to
+/// the row above, which has the lower y coordinate, so the rows can be read
+/// from top to bottom like text.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Direction2D {
+    Left,
+    Right,
+    Up,
+    Down,
+    Hold
+}
+
+impl Direction2D {
+    /// Parse a direction from a string. Returns None, if it is not a known
+    /// direction.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str<S: AsRef<str>>(s: S) -> Option<Direction2D> {
+        match s.as_ref().to_lowercase().as_ref() {
+            "left" => Some(Direction2D::Left),
+            "right" => Some(Direction2D::Right),
+            "up" => Some(Direction2D::Up),
+            "down" => Some(Direction2D::Down),
+            "hold" => Some(Direction2D::Hold),
+            _ => None
+        }
+    }
+
+    /// The change of the (x, y) position when moving in this direction.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Direction2D::Left => (-1, 0),
+            Direction2D::Right => (1, 0),
+            Direction2D::Up => (0, -1),
+            Direction2D::Down => (0, 1),
+            Direction2D::Hold => (0, 0)
+        }
+    }
+}
+
+/// Two dimensional tape, which is infinite in all directions. Only the cells
+/// that are not blank are stored.
+#[derive(Clone)]
+pub struct GridTape<V: Tapeable> {
+    cells: HashMap<(isize, isize), V>,
+    /// The current head position as (x, y)
+    head_position: (isize, isize)
+}
+
+impl<V: Tapeable> Default for GridTape<V> {
+    fn default() -> GridTape<V> {
+        GridTape::new()
+    }
+}
+
+impl<V: Tapeable> GridTape<V> {
+    /// Create a new, empty grid with the head at (0, 0)
+    pub fn new() -> GridTape<V> {
+        GridTape {
+            cells: HashMap::new(),
+            head_position: (0, 0)
+        }
+    }
+
+    /// Create a grid from the rows, starting with the top one. The first cell of
+    /// the first row is at (0, 0), which is also where the head starts.
+    pub fn from_rows(rows: Vec<Vec<Option<V>>>) -> GridTape<V> {
+        let mut grid = GridTape::new();
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, v) in row.into_iter().enumerate() {
+                grid.write_at((x as isize, y as isize), v);
+            }
+        }
+        grid
+    }
+
+    /// Move the head and return the value that is found there.
+    pub fn mv(&mut self, direction: Direction2D) -> Option<V> {
+        let (dx, dy) = direction.offset();
+        self.head_position = (self.head_position.0 + dx, self.head_position.1 + dy);
+        self.read()
+    }
+
+    /// Read the value under the head.
+    pub fn read(&self) -> Option<V> {
+        self.read_at(self.head_position)
+    }
+
+    pub fn write(&mut self, val: Option<V>) {
+        self.write_at(self.head_position, val);
+    }
+
+    /// Read the value at any position, regardless of where the head is.
+    pub fn read_at(&self, position: (isize, isize)) -> Option<V> {
+        self.cells.get(&position).copied()
+    }
+
+    fn write_at(&mut self, position: (isize, isize), val: Option<V>) {
+        match val {
+            Some(v) => { self.cells.insert(position, v); },
+            None => { self.cells.remove(&position); }
+        }
+    }
+
+    pub fn head_position(&self) -> (isize, isize) {
+        self.head_position
+    }
+
+    /// The smallest rectangle containing all cells that are not blank, as the
+    /// ranges of x and y coordinates. Both are empty, if the grid is.
+    pub fn bounds(&self) -> (Range<isize>, Range<isize>) {
+        if self.cells.is_empty() {
+            return (0..0, 0..0);
+        }
+
+        let xs = self.cells.keys().map(|(x, _)| *x);
+        let ys = self.cells.keys().map(|(_, y)| *y);
+        (xs.clone().min().unwrap()..xs.max().unwrap() + 1, ys.clone().min().unwrap()..ys.max().unwrap() + 1)
+    }
+
+    /// Export the contents within the bounds, row by row from the top.
+    pub fn rows(&self) -> Vec<Vec<Option<V>>> {
+        let (xs, ys) = self.bounds();
+        ys.map(|y| xs.clone().map(|x| self.read_at((x, y))).collect()).collect()
+    }
+}
+
+impl<V: Tapeable> Display for GridTape<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for row in self.rows() {
+            for v in row {
+                if let Some(v) = v { write!(f, "{} ", v)?; }
+                else { write!(f, "NONE ")?; }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Deterministic turing machine on a two dimensional tape. It uses the same
+/// kind of transition table as the one dimensional machine, only with the
+/// directions extended by Up and Down.
+pub struct GridMachine<V: Tapeable, S: Stateable = State> {
+    starting_state: S,
+    current_state: S,
+    grid: GridTape<V>,
+    /// Copy of the grid as it was inserted, used to restore it on reset
+    initial_grid: GridTape<V>,
+    transitions: TransitionTable<V, S, Direction2D>,
+    final_states: FinalStates<S>,
+    /// Number of transitions performed since the last reset
+    steps: usize
+}
+
+impl<V: Tapeable, S: Stateable + Default> GridMachine<V, S> {
+    /// Create a new machine on the grid, with an empty transition function. It
+    /// starts in the default state, which is 0 for numbered states.
+    pub fn new(grid: GridTape<V>) -> GridMachine<V, S> {
+        GridMachine::init_fully(grid, HashMap::new(), S::default())
+    }
+}
+
+impl<V: Tapeable, S: Stateable> GridMachine<V, S> {
+    /// Initialise the machine fully. Needs the grid containing the input, the
+    /// full transition table and the state the machine will start from.
+    pub fn init_fully(grid: GridTape<V>, transitions: TransitionTable<V, S, Direction2D>, starting_state: S) -> GridMachine<V, S> {
+        GridMachine {
+            current_state: starting_state.clone(),
+            starting_state,
+            initial_grid: grid.clone(),
+            grid,
+            transitions,
+            final_states: FinalStates::default(),
+            steps: 0
+        }
+    }
+
+    /// Add a transition to the transition table. If there was already a
+    /// transition registered to the cause, it will be replaced and returned
+    pub fn add_transition(&mut self, cause: (S, Option<V>), effect: (S, Option<V>, Direction2D)) -> Option<(S, Option<V>, Direction2D)> {
+        self.transitions.insert(cause, effect)
+    }
+
+    /// Reset the machine to its starting state and restore the grid as it was
+    /// inserted.
+    pub fn reset(&mut self) {
+        self.current_state = self.starting_state.clone();
+        self.grid = self.initial_grid.clone();
+        self.steps = 0;
+    }
+
+    /// Get the verdict of the machine, if it is currently in a final state.
+    pub fn verdict(&self) -> Option<Verdict> {
+        self.final_states.verdict(&self.current_state)
+    }
+
+    /// The state the machine is currently in.
+    pub fn current_state(&self) -> &S {
+        &self.current_state
+    }
+
+    /// The number of transitions that have been performed since the machine
+    /// has last been reset.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn grid(&self) -> &GridTape<V> {
+        &self.grid
+    }
+
+    /// Check what the next transition will be, without actually performing it.
+    /// Returns None, if the transition function is not defined for the current
+    /// state and the symbol under the head.
+    pub fn peek_transition(&self) -> Option<(S, Option<V>, Direction2D)> {
+        self.transitions.get(&(self.current_state.clone(), self.grid.read())).cloned()
+    }
+
+    /// Perform the next transition. If the transition function is not defined
+    /// for the current configuration, nothing is changed.
+    pub fn step(&mut self) -> StepOutcome<V, S, (isize, isize)> {
+        if let Some(verdict) = self.verdict() {
+            return StepOutcome::Halted(verdict);
+        }
+
+        let (new_state, value, dir) = match self.peek_transition() {
+            Some(transition) => transition,
+            None => return StepOutcome::NoTransition {
+                state: self.current_state.clone(),
+                symbol: self.grid.read(),
+                head_position: self.grid.head_position()
+            }
+        };
+
+        self.current_state = new_state;
+        self.grid.write(value);
+        self.grid.mv(dir);
+        self.steps += 1;
+
+        match self.verdict() {
+            Some(verdict) => StepOutcome::Halted(verdict),
+            None => StepOutcome::Continued
+        }
+    }
+
+    /// Step the machine until it halts or gets stuck, performing at most
+    /// max_steps transitions.
+    pub fn run(&mut self, max_steps: usize) -> RunOutcome<V, S, (isize, isize)> {
+        for _ in 0..max_steps {
+            if let Some(outcome) = self.step().into_run_outcome() {
+                return outcome;
+            }
+        }
+
+        match self.verdict() {
+            Some(verdict) => RunOutcome::Halted(verdict),
+            None => RunOutcome::StepLimitReached
+        }
+    }
+}
+
+impl<V: Tapeable, S: Stateable> Halting<S> for GridMachine<V, S> {
+    fn final_states(&self) -> &FinalStates<S> {
+        &self.final_states
+    }
+
+    fn final_states_mut(&mut self) -> &mut FinalStates<S> {
+        &mut self.final_states
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Direction2D::*;
+
+    /// Langton's ant, the state being the direction the ant is facing
+    fn langtons_ant() -> GridMachine<bool, &'static str> {
+        let mut tm = GridMachine::init_fully(GridTape::new(), HashMap::new(), "north");
+        let clockwise = [("north", Up), ("east", Right), ("south", Down), ("west", Left)];
+        for i in 0..4 {
+            let right = clockwise[(i + 1) % 4];
+            let left = clockwise[(i + 3) % 4];
+            // Turn right on white, left on black, flip the colour and move on
+            tm.add_transition((clockwise[i].0, None), (right.0, Some(true), right.1));
+            tm.add_transition((clockwise[i].0, Some(true)), (left.0, None, left.1));
+        }
+        tm
+    }
+
+    #[test]
+    fn test_langtons_ant() {
+        let mut tm = langtons_ant();
+
+        assert_eq!(tm.run(4), RunOutcome::StepLimitReached);
+        assert_eq!(tm.grid().rows(), vec![vec![Some(true), Some(true)], vec![Some(true), Some(true)]]);
+        assert_eq!(tm.grid().head_position(), (0, 0));
+        assert_eq!(*tm.current_state(), "north");
+
+        tm.step();
+        assert_eq!(tm.grid().bounds(), (0..2, 0..2));
+        assert_eq!(tm.grid().rows(), vec![vec![None, Some(true)], vec![Some(true), Some(true)]]);
+        assert_eq!(tm.grid().head_position(), (-1, 0));
+
+        tm.reset();
+        assert_eq!(tm.grid().rows(), Vec::<Vec<Option<bool>>>::new());
+    }
+}
@@ -58,7 +58,7 @@ fn handle_inside_tuple(c: char, res: &mut Vec<Lex>, t: &mut Vec<Option<String>>,
     // element to the result vector if so
     if c == ')' {
         // Push the last tuple element, which might remain in the buffer
-        if v.trim().len() != 0 {
+        if !v.trim().is_empty() {
             if v.trim() == "None" {
                 t.push(None);
             }
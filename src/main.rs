@@ -1,13 +1,41 @@
 #![allow(dead_code)]
 #![feature(trait_alias)]
 
+mod bit_tape;
+mod breakpoint;
+mod enumerator;
+mod grid;
 mod lexicaliser;
+mod multi_head;
+mod multi_tape;
+mod nondeterministic;
+mod observer;
+mod oracle;
 mod parser;
+mod probabilistic;
+mod render;
+mod rle_tape;
+mod stats;
 mod tape;
+mod tracks;
 mod turing_machine;
 
+pub use bit_tape::*;
+pub use breakpoint::*;
+pub use enumerator::*;
+pub use grid::*;
 pub use lexicaliser::*;
+pub use multi_head::*;
+pub use multi_tape::*;
+pub use nondeterministic::*;
+pub use observer::*;
+pub use oracle::*;
+pub use probabilistic::*;
+pub use render::*;
+pub use rle_tape::*;
+pub use stats::*;
 pub use tape::*;
+pub use tracks::*;
 pub use turing_machine::*;
 
 fn main() {
@@ -26,11 +54,12 @@ mod test {
         // Transitions for a simple turing machine that just inverts the input
         tm.add_transition((0, Some(true)), (0, Some(false), Direction::Right));
         tm.add_transition((0, Some(false)), (0, Some(true), Direction::Right));
-        tm.add_transition((0, None), (0, None, Direction::Hold));
+        tm.add_transition((0, None), (1, None, Direction::Hold));
+        tm.add_halting_state(1);
 
-        while tm.step() {
-            println!("Stepping TM");
-        }
+        let report = tm.run(1000, None);
+        assert_eq!(report.outcome, RunOutcome::Halted(Verdict::Halt));
+        assert_eq!(report.steps, 7);
 
         println!("TM finished:");
         tm.print();
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use crate::multi_tape::{MultiEffect, MultiStepOutcome, MultiTransitionable, MultiTransitionTable};
+use crate::tape::{Direction, SimpleTape, Tape, Tapeable};
+use crate::turing_machine::{FinalStates, Halting, State, Stateable, Verdict};
+
+/// What happens, when multiple heads write to the same cell in the same step
+/// and do not agree on the value. Heads writing the same value never conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteConflict {
+    /// The head with the lowest index wins.
+    FirstHead,
+    /// The head with the highest index wins.
+    LastHead,
+    /// Nothing is written and the step fails.
+    Reject
+}
+
+/// A single infinite tape with several heads, which can be moved independently
+/// of each other. All heads start at position 0.
+#[derive(Clone)]
+pub struct MultiHeadTape<V: Tapeable> {
+    /// The cells, the head of this tape is not used
+    cells: Tape<V>,
+    heads: Vec<isize>
+}
+
+impl<V: Tapeable> MultiHeadTape<V> {
+    /// Create an empty tape with the given number of heads.
+    pub fn new(heads: usize) -> MultiHeadTape<V> {
+        MultiHeadTape::tape(Vec::new(), heads)
+    }
+
+    /// Create a tape from the values in the vector, with the given number of
+    /// heads.
+    pub fn tape(tape: Vec<Option<V>>, heads: usize) -> MultiHeadTape<V> {
+        MultiHeadTape {
+            cells: Tape::tape(tape),
+            heads: vec![0; heads]
+        }
+    }
+
+    pub fn head_count(&self) -> usize {
+        self.heads.len()
+    }
+
+    pub fn head_position(&self, head: usize) -> isize {
+        self.heads[head]
+    }
+
+    /// Read the value under the head.
+    pub fn read(&self, head: usize) -> Option<V> {
+        self.cells.read_at(self.heads[head])
+    }
+
+    /// Read the values under all heads, in the order of the heads.
+    pub fn read_all(&self) -> Vec<Option<V>> {
+        self.heads.iter().map(|&p| self.cells.read_at(p)).collect()
+    }
+
+    /// Move the head in the direction and return the value found there.
+    pub fn mv(&mut self, head: usize, direction: Direction) -> Option<V> {
+        self.heads[head] += direction as isize;
+        self.read(head)
+    }
+
+    /// Write one value under each head at the same time. If heads on the same
+    /// cell want to write different values, the rule decides what is written.
+    /// With WriteConflict::Reject, nothing is written in that case and the
+    /// position of the first conflict is returned.
+    pub fn write_all(&mut self, values: &[Option<V>], rule: WriteConflict) -> Result<(), isize> {
+        let mut writes: Vec<(isize, Option<V>)> = Vec::with_capacity(self.heads.len());
+        for (&position, &value) in self.heads.iter().zip(values) {
+            match writes.iter_mut().find(|(p, _)| *p == position) {
+                Some((_, written)) if *written != value => match rule {
+                    WriteConflict::FirstHead => {},
+                    WriteConflict::LastHead => *written = value,
+                    WriteConflict::Reject => return Err(position)
+                },
+                Some(_) => {},
+                None => writes.push((position, value))
+            }
+        }
+
+        for (position, value) in writes {
+            self.cells.write_at(position, value);
+        }
+        Ok(())
+    }
+
+    /// Get the tape contents. May contain leading or trailing blanks
+    pub fn contents(&self) -> Vec<Option<V>> {
+        self.cells.contents()
+    }
+
+    /// Like contents, but removes leading and trailing blanks. Blanks in the
+    /// middle are accepted
+    pub fn contents_trim_blanks(&self) -> Vec<Option<V>> {
+        self.cells.contents_trim_blanks()
+    }
+}
+
+impl<V: Tapeable> Display for MultiHeadTape<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{} heads at {:?}", self.cells, self.heads)
+    }
+}
+
+/// Deterministic turing machine with several heads on a single tape. The
+/// transitions read the symbols under all heads and move every head on its
+/// own.
+pub struct MultiHeadMachine<V: Tapeable, S: Stateable = State> {
+    starting_state: S,
+    current_state: S,
+    tape: MultiHeadTape<V>,
+    /// Copy of the tape as it was inserted or loaded, used to restore it on
+    /// reset
+    initial_tape: MultiHeadTape<V>,
+    transitions: MultiTransitionTable<V, S>,
+    final_states: FinalStates<S>,
+    write_conflict: WriteConflict,
+    /// Number of transitions performed since the last reset
+    steps: usize
+}
+
+impl<V: Tapeable, S: Stateable + Default> MultiHeadMachine<V, S> {
+    /// Create a new machine working on the tape, with an empty transition
+    /// function. It starts in the default state, which is 0 for numbered
+    /// states.
+    pub fn new(tape: MultiHeadTape<V>) -> MultiHeadMachine<V, S> {
+        MultiHeadMachine::with_starting_state(tape, S::default())
+    }
+}
+
+impl<V: Tapeable, S: Stateable> MultiHeadMachine<V, S> {
+    /// Create a new machine working on the tape, with an empty transition
+    /// function and the given starting state. Conflicting writes are rejected
+    /// by default.
+    pub fn with_starting_state(tape: MultiHeadTape<V>, starting_state: S) -> MultiHeadMachine<V, S> {
+        MultiHeadMachine {
+            current_state: starting_state.clone(),
+            starting_state,
+            initial_tape: tape.clone(),
+            tape,
+            transitions: HashMap::new(),
+            final_states: FinalStates::default(),
+            write_conflict: WriteConflict::Reject,
+            steps: 0
+        }
+    }
+
+    /// Change what happens, when heads on the same cell write different values.
+    pub fn set_write_conflict(&mut self, rule: WriteConflict) {
+        self.write_conflict = rule;
+    }
+
+    /// Add a transition to the transition table. The symbols and directions
+    /// need to have exactly one entry per head. If there was already a
+    /// transition registered to the cause, it will be replaced and returned.
+    ///
+    /// # Panics
+    /// If the number of symbols or directions does not match the number of
+    /// heads.
+    pub fn add_transition(&mut self, cause: (S, Vec<Option<V>>), effect: MultiEffect<V, S>) -> Option<MultiEffect<V, S>> {
+        let k = self.tape.head_count();
+        assert!(cause.1.len() == k && effect.1.len() == k && effect.2.len() == k, "Transition does not fit a machine with {} heads", k);
+        self.transitions.insert(cause, effect)
+    }
+
+    /// Load the input onto the tape, with all heads on its first symbol, and
+    /// reset the machine. The input is remembered for every following reset.
+    pub fn load_input(&mut self, input: &[Option<V>]) {
+        self.initial_tape = MultiHeadTape::tape(input.to_vec(), self.tape.head_count());
+        self.reset();
+    }
+
+    /// Reset the machine to its starting state and restore the tape as it was
+    /// inserted or loaded last.
+    pub fn reset(&mut self) {
+        self.current_state = self.starting_state.clone();
+        self.tape = self.initial_tape.clone();
+        self.steps = 0;
+    }
+
+    /// The state the machine is currently in.
+    pub fn current_state(&self) -> &S {
+        &self.current_state
+    }
+
+    /// The number of transitions that have been performed since the machine
+    /// has last been reset.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn tape(&self) -> &MultiHeadTape<V> {
+        &self.tape
+    }
+
+    /// Check what the next transition will be, without actually performing it.
+    /// Returns None, if the transition function is not defined for the current
+    /// state and the symbols under the heads.
+    pub fn peek_transition(&self) -> Option<&MultiEffect<V, S>> {
+        self.transitions.get(&(self.current_state.clone(), self.tape.read_all()))
+    }
+}
+
+impl<V: Tapeable, S: Stateable> Halting<S> for MultiHeadMachine<V, S> {
+    fn final_states(&self) -> &FinalStates<S> {
+        &self.final_states
+    }
+
+    fn final_states_mut(&mut self) -> &mut FinalStates<S> {
+        &mut self.final_states
+    }
+}
+
+impl<V: Tapeable, S: Stateable> MultiTransitionable<V, S> for MultiHeadMachine<V, S> {
+    fn verdict(&self) -> Option<Verdict> {
+        self.final_states.verdict(&self.current_state)
+    }
+
+    /// Perform the next transition. All heads write at the same time, before
+    /// any of them is moved. If the transition function is not defined or the
+    /// writes conflict and are rejected, nothing is changed.
+    fn step(&mut self) -> MultiStepOutcome<V, S> {
+        if let Some(verdict) = self.verdict() {
+            return MultiStepOutcome::Halted(verdict);
+        }
+
+        let (new_state, values, directions) = match self.peek_transition() {
+            Some(transition) => transition.clone(),
+            None => return MultiStepOutcome::NoTransition {
+                state: self.current_state.clone(),
+                symbols: self.tape.read_all(),
+                head_positions: self.tape.heads.clone()
+            }
+        };
+
+        if let Err(position) = self.tape.write_all(&values, self.write_conflict) {
+            return MultiStepOutcome::WriteConflict { state: self.current_state.clone(), position };
+        }
+        for (head, direction) in directions.into_iter().enumerate() {
+            self.tape.mv(head, direction);
+        }
+        self.current_state = new_state;
+        self.steps += 1;
+
+        match self.verdict() {
+            Some(verdict) => MultiStepOutcome::Halted(verdict),
+            None => MultiStepOutcome::Continued
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Direction::*;
+    use crate::multi_tape::MultiRunOutcome;
+
+    #[test]
+    fn test_palindrome() {
+        // The second head walks to the end of the input, then both heads walk
+        // towards each other, comparing the symbols they read.
+        let mut tm = MultiHeadMachine::new(MultiHeadTape::new(2));
+        for x in ['a', 'b'] {
+            for y in ['a', 'b'] {
+                tm.add_transition((0, vec![Some(x), Some(y)]), (0, vec![Some(x), Some(y)], vec![Hold, Right]));
+                let next = if x == y { 1 } else { 3 };
+                tm.add_transition((1, vec![Some(x), Some(y)]), (next, vec![Some(x), Some(y)], vec![Right, Left]));
+            }
+            tm.add_transition((0, vec![Some(x), None]), (1, vec![Some(x), None], vec![Hold, Left]));
+        }
+        tm.add_transition((0, vec![None, None]), (2, vec![None, None], vec![Hold, Hold]));
+        tm.add_transition((1, vec![None, None]), (2, vec![None, None], vec![Hold, Hold]));
+        tm.add_accepting_state(2);
+        tm.add_rejecting_state(3);
+
+        for (word, verdict) in [("abba", Verdict::Accept), ("aba", Verdict::Accept), ("", Verdict::Accept), ("abab", Verdict::Reject)] {
+            tm.load_input(&word.chars().map(Some).collect::<Vec<_>>());
+            assert_eq!(tm.run(100), MultiRunOutcome::Halted(verdict), "{}", word);
+        }
+    }
+
+    #[test]
+    fn test_write_conflicts() {
+        let mut tape = MultiHeadTape::tape(vec![Some(0), Some(0)], 3);
+        tape.mv(2, Right);
+
+        assert_eq!(tape.write_all(&[Some(1), Some(2), Some(3)], WriteConflict::Reject), Err(0));
+        assert_eq!(tape.contents(), vec![Some(0), Some(0)]);
+
+        tape.write_all(&[Some(1), Some(2), Some(3)], WriteConflict::FirstHead).unwrap();
+        assert_eq!(tape.contents(), vec![Some(1), Some(3)]);
+
+        tape.write_all(&[Some(1), Some(2), Some(4)], WriteConflict::LastHead).unwrap();
+        assert_eq!(tape.contents(), vec![Some(2), Some(4)]);
+
+        // Agreeing heads are fine with every rule
+        tape.write_all(&[Some(5), Some(5), Some(4)], WriteConflict::Reject).unwrap();
+        assert_eq!(tape.contents(), vec![Some(5), Some(4)]);
+    }
+}
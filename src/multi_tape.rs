@@ -0,0 +1,372 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Deref;
+use crate::tape::{Direction, SimpleTape, TapeError, Tapeable};
+use crate::turing_machine::{FinalStates, Halting, State, Stateable, Verdict};
+
+/// Transitions of a machine with multiple tapes, or multiple heads. The
+/// symbols read, the symbols written and the directions the heads are moved
+/// in have one entry per tape or head each.
+pub type MultiTransitionTable<V, S = State> = HashMap<(S, Vec<Option<V>>), MultiEffect<V, S>>;
+
+/// The state to change to, the symbols to write and the directions to move
+/// the heads in.
+pub type MultiEffect<V, S = State> = (S, Vec<Option<V>>, Vec<Direction>);
+
+/// The result of performing a single step of a machine with multiple tapes or
+/// heads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiStepOutcome<V, S = State> {
+    /// A transition has been performed and the machine wants to continue.
+    Continued,
+    /// The machine is in a final state and will not do anything anymore.
+    Halted(Verdict),
+    /// There is no transition for the state and the symbols under the heads.
+    /// The machine stays exactly as it was before the step.
+    NoTransition {
+        state: S,
+        symbols: Vec<Option<V>>,
+        head_positions: Vec<isize>
+    },
+    /// Several heads on the same tape wanted to write different values to the
+    /// cell at the position and the conflict has been rejected. The machine
+    /// stays exactly as it was before the step.
+    WriteConflict {
+        state: S,
+        position: isize
+    },
+    /// One of the tapes refused the transition, for example because its head
+    /// would have left the bounds. The machine stays exactly as it was before
+    /// the step.
+    TapeError(TapeError<V>)
+}
+
+/// The reason a bounded run of a machine with multiple tapes or heads has
+/// ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiRunOutcome<V, S = State> {
+    /// The machine has halted with the given verdict.
+    Halted(Verdict),
+    /// The machine got stuck, because there was no transition defined for the
+    /// state and the symbols under the heads.
+    NoTransition {
+        state: S,
+        symbols: Vec<Option<V>>,
+        head_positions: Vec<isize>
+    },
+    /// The machine got stuck, because several heads wanted to write different
+    /// values to the same cell.
+    WriteConflict {
+        state: S,
+        position: isize
+    },
+    /// The machine got stuck, because a transition could not be performed on
+    /// one of the tapes.
+    TapeError(TapeError<V>),
+    /// The step budget has been used up before the machine halted.
+    StepLimitReached
+}
+
+impl<V, S> MultiStepOutcome<V, S> {
+    /// The outcome of a run that ends with this step, or None if the machine
+    /// wants to continue.
+    pub(crate) fn into_run_outcome(self) -> Option<MultiRunOutcome<V, S>> {
+        match self {
+            MultiStepOutcome::Continued => None,
+            MultiStepOutcome::Halted(verdict) => Some(MultiRunOutcome::Halted(verdict)),
+            MultiStepOutcome::NoTransition { state, symbols, head_positions } => {
+                Some(MultiRunOutcome::NoTransition { state, symbols, head_positions })
+            },
+            MultiStepOutcome::WriteConflict { state, position } => {
+                Some(MultiRunOutcome::WriteConflict { state, position })
+            },
+            MultiStepOutcome::TapeError(err) => Some(MultiRunOutcome::TapeError(err))
+        }
+    }
+}
+
+/// Machines with several tapes or heads, whose steps are reported as a
+/// MultiStepOutcome.
+pub trait MultiTransitionable<V, S = State> {
+    /// Get the verdict of the machine, if it is currently in a final state.
+    fn verdict(&self) -> Option<Verdict>;
+
+    /// Perform the next step. If it cannot be performed, nothing is changed.
+    fn step(&mut self) -> MultiStepOutcome<V, S>;
+
+    /// Step the machine until it halts or gets stuck, performing at most
+    /// max_steps steps.
+    fn run(&mut self, max_steps: usize) -> MultiRunOutcome<V, S> {
+        for _ in 0..max_steps {
+            if let Some(outcome) = self.step().into_run_outcome() {
+                return outcome;
+            }
+        }
+
+        match self.verdict() {
+            Some(verdict) => MultiRunOutcome::Halted(verdict),
+            None => MultiRunOutcome::StepLimitReached
+        }
+    }
+}
+
+/// Deterministic turing machine with k tapes, each having its own head.
+pub struct MultiTapeMachine<V: Tapeable, S: Stateable = State> {
+    starting_state: S,
+    current_state: S,
+    tapes: Vec<Box<dyn SimpleTape<V>>>,
+    /// Copies of the tapes as they were inserted or loaded, used to restore
+    /// them on reset
+    initial_tapes: Vec<Box<dyn SimpleTape<V>>>,
+    transitions: MultiTransitionTable<V, S>,
+    final_states: FinalStates<S>,
+    /// Number of transitions performed since the last reset
+    steps: usize
+}
+
+impl<V: Tapeable, S: Stateable + Default> MultiTapeMachine<V, S> {
+    /// Create a new machine working on the tapes, with an empty transition
+    /// function. It starts in the default state, which is 0 for numbered
+    /// states.
+    pub fn new(tapes: Vec<Box<dyn SimpleTape<V>>>) -> MultiTapeMachine<V, S> {
+        MultiTapeMachine::with_starting_state(tapes, S::default())
+    }
+}
+
+impl<V: Tapeable, S: Stateable> MultiTapeMachine<V, S> {
+    /// Create a new machine working on the tapes, with an empty transition
+    /// function and the given starting state.
+    pub fn with_starting_state(tapes: Vec<Box<dyn SimpleTape<V>>>, starting_state: S) -> MultiTapeMachine<V, S> {
+        MultiTapeMachine {
+            current_state: starting_state.clone(),
+            starting_state,
+            initial_tapes: tapes.iter().map(|t| t.boxed_clone()).collect(),
+            tapes,
+            transitions: HashMap::new(),
+            final_states: FinalStates::default(),
+            steps: 0
+        }
+    }
+
+    /// Add a transition to the transition table. The symbols and directions
+    /// need to have exactly one entry per tape. If there was already a
+    /// transition registered to the cause, it will be replaced and returned.
+    ///
+    /// # Panics
+    /// If the number of symbols or directions does not match the number of
+    /// tapes.
+    pub fn add_transition(&mut self, cause: (S, Vec<Option<V>>), effect: MultiEffect<V, S>) -> Option<MultiEffect<V, S>> {
+        let k = self.tapes.len();
+        assert!(cause.1.len() == k && effect.1.len() == k && effect.2.len() == k, "Transition does not fit a machine with {} tapes", k);
+        self.transitions.insert(cause, effect)
+    }
+
+    /// Load the input onto the first tape and clear all others, then reset the
+    /// machine. The input is remembered for every following reset.
+    /// If the input contains symbols outside of the input alphabet of the first
+    /// tape, an error is returned and the machine is left unchanged.
+    pub fn load_input(&mut self, input: &[Option<V>]) -> Result<(), TapeError<V>> {
+        // The first tape is the only one that can refuse, so nothing has been
+        // changed when it does
+        for (i, tape) in self.initial_tapes.iter_mut().enumerate() {
+            tape.try_load(if i == 0 { input } else { &[] })?;
+        }
+        self.reset();
+        Ok(())
+    }
+
+    /// Reset the machine to its starting state and restore the tapes as they
+    /// were inserted or loaded last.
+    pub fn reset(&mut self) {
+        self.current_state = self.starting_state.clone();
+        self.tapes = self.initial_tapes.iter().map(|t| t.boxed_clone()).collect();
+        self.steps = 0;
+    }
+
+    /// The state the machine is currently in.
+    pub fn current_state(&self) -> &S {
+        &self.current_state
+    }
+
+    /// The number of transitions that have been performed since the machine
+    /// has last been reset.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn tape(&self, i: usize) -> &dyn SimpleTape<V> {
+        self.tapes[i].deref()
+    }
+
+    pub(crate) fn tape_mut(&mut self, i: usize) -> &mut dyn SimpleTape<V> {
+        self.tapes[i].as_mut()
+    }
+
+    /// Change to the state without performing a transition, but count it as a
+    /// step. Used by machines that add their own kind of steps on top.
+    pub(crate) fn enter_state(&mut self, state: S) {
+        self.current_state = state;
+        self.steps += 1;
+    }
+
+    pub fn tape_count(&self) -> usize {
+        self.tapes.len()
+    }
+
+    /// The symbols currently under the heads, one per tape.
+    pub fn read(&self) -> Vec<Option<V>> {
+        self.tapes.iter().map(|t| t.read()).collect()
+    }
+
+    /// Check what the next transition will be, without actually performing it.
+    /// Returns None, if the transition function is not defined for the current
+    /// state and the symbols under the heads.
+    pub fn peek_transition(&self) -> Option<&MultiEffect<V, S>> {
+        self.transitions.get(&(self.current_state.clone(), self.read()))
+    }
+}
+
+impl<V: Tapeable, S: Stateable> Halting<S> for MultiTapeMachine<V, S> {
+    fn final_states(&self) -> &FinalStates<S> {
+        &self.final_states
+    }
+
+    fn final_states_mut(&mut self) -> &mut FinalStates<S> {
+        &mut self.final_states
+    }
+}
+
+impl<V: Tapeable, S: Stateable> MultiTransitionable<V, S> for MultiTapeMachine<V, S> {
+    fn verdict(&self) -> Option<Verdict> {
+        self.final_states.verdict(&self.current_state)
+    }
+
+    /// Perform the next transition. If the transition function is not defined
+    /// for the current state and symbols, nothing is changed.
+    fn step(&mut self) -> MultiStepOutcome<V, S> {
+        if let Some(verdict) = self.verdict() {
+            return MultiStepOutcome::Halted(verdict);
+        }
+
+        let (new_state, values, directions) = match self.peek_transition() {
+            Some(transition) => transition.clone(),
+            None => return MultiStepOutcome::NoTransition {
+                state: self.current_state.clone(),
+                symbols: self.read(),
+                head_positions: self.tapes.iter().map(|t| t.head_position()).collect()
+            }
+        };
+
+        // Every tape is written and moved independently of the others. If one
+        // of them refuses, the tapes done so far are restored, so that the
+        // machine is left as it was
+        let mut previous = Vec::with_capacity(self.tapes.len());
+        for (i, (value, direction)) in values.into_iter().zip(directions).enumerate() {
+            let tape = &mut self.tapes[i];
+            previous.push((tape.read(), tape.head_position()));
+            if let Err(err) = tape.try_write(value).and_then(|_| tape.try_mv(direction)) {
+                for (tape, (symbol, position)) in self.tapes.iter_mut().zip(previous) {
+                    undo_step(tape.as_mut(), symbol, position);
+                }
+                return MultiStepOutcome::TapeError(err);
+            }
+        }
+        self.current_state = new_state;
+        self.steps += 1;
+
+        match self.verdict() {
+            Some(verdict) => MultiStepOutcome::Halted(verdict),
+            None => MultiStepOutcome::Continued
+        }
+    }
+}
+
+/// Move the head back to the position it had before a step and restore the
+/// symbol found there.
+fn undo_step<V>(tape: &mut dyn SimpleTape<V>, symbol: Option<V>, position: isize) {
+    match tape.head_position().cmp(&position) {
+        Ordering::Less => { tape.mv_right(); },
+        Ordering::Greater => { tape.mv_left(); },
+        Ordering::Equal => {}
+    }
+    tape.write(symbol);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::{Alphabet, Boundary, Tape};
+    use Direction::*;
+
+    #[test]
+    fn test_copy() {
+        let mut tm = MultiTapeMachine::new(vec![Box::new(Tape::new()), Box::new(Tape::new())]);
+        for a in [0, 1] {
+            tm.add_transition((0, vec![Some(a), None]), (0, vec![Some(a), Some(a)], vec![Right, Right]));
+        }
+        tm.add_transition((0, vec![None, None]), (1, vec![None, None], vec![Hold, Left]));
+        tm.add_halting_state(1);
+
+        assert_eq!(tm.load_input(&[Some(1), Some(0), Some(1), Some(1)]), Ok(()));
+        assert_eq!(tm.run(100), MultiRunOutcome::Halted(Verdict::Halt));
+        assert_eq!(tm.tape(1).contents_trim_blanks(), vec![Some(1), Some(0), Some(1), Some(1)]);
+        assert_eq!(tm.tape(0).head_position(), 4);
+        assert_eq!(tm.tape(1).head_position(), 3);
+
+        tm.reset();
+        assert_eq!(tm.run(2), MultiRunOutcome::StepLimitReached);
+    }
+
+    #[test]
+    fn test_compare() {
+        let tapes: Vec<Box<dyn SimpleTape<char>>> = vec![
+            Box::new(Tape::tape(vec![Some('a'), Some('b')])),
+            Box::new(Tape::tape(vec![Some('a'), Some('c')]))
+        ];
+        let mut tm = MultiTapeMachine::with_starting_state(tapes, "compare");
+        for a in ['a', 'b', 'c'] {
+            tm.add_transition(("compare", vec![Some(a), Some(a)]), ("compare", vec![Some(a), Some(a)], vec![Right, Right]));
+        }
+        tm.add_transition(("compare", vec![None, None]), ("equal", vec![None, None], vec![Hold, Hold]));
+        tm.add_accepting_state("equal");
+
+        assert_eq!(tm.run(100), MultiRunOutcome::NoTransition {
+            state: "compare",
+            symbols: vec![Some('b'), Some('c')],
+            head_positions: vec![1, 1]
+        });
+    }
+
+    #[test]
+    fn test_bounded_tape() {
+        // Copies the input, but walks past its end on the bounded tape
+        let tapes: Vec<Box<dyn SimpleTape<char>>> = vec![
+            Box::new(Tape::new()),
+            Box::new(Tape::linear_bounded(vec![Some('a')], '<', '>', Boundary::Error))
+        ];
+        let mut tm = MultiTapeMachine::new(tapes);
+        tm.add_transition((0, vec![None, Some('a')]), (0, vec![Some('a'), Some('a')], vec![Right, Right]));
+        tm.add_transition((0, vec![None, Some('>')]), (1, vec![Some('x'), Some('>')], vec![Right, Right]));
+
+        // The copy tape is restored, when the bounded tape refuses to move
+        assert_eq!(tm.run(5), MultiRunOutcome::TapeError(TapeError::OutOfBounds { position: 2 }));
+        assert_eq!(tm.tape(0).contents_trim_blanks(), vec![Some('a')]);
+        assert_eq!(tm.tape(0).head_position(), 1);
+        assert_eq!(tm.tape(1).head_position(), 1);
+        assert_eq!(*tm.current_state(), 0);
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let mut binary = Tape::new();
+        binary.set_alphabet(Some(Alphabet::new([0, 1], [], None)));
+        let mut tm = MultiTapeMachine::new(vec![Box::new(Tape::new()), Box::new(binary)]);
+        tm.add_transition((0, vec![Some(1), None]), (0, vec![Some(0), Some(2)], vec![Right, Right]));
+
+        // Writing outside the alphabet of the second tape restores the first
+        assert_eq!(tm.load_input(&[Some(1)]), Ok(()));
+        assert_eq!(tm.run(5), MultiRunOutcome::TapeError(TapeError::SymbolOutsideAlphabet { symbol: Some(2), position: 0 }));
+        assert_eq!(tm.tape(0).contents_trim_blanks(), vec![Some(1)]);
+        assert_eq!(tm.tape(0).head_position(), 0);
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::Deref;
+use crate::tape::{Direction, SimpleTape, TapeError, Tapeable};
+use crate::turing_machine::{FinalStates, Halting, State, Stateable, Verdict};
+
+/// The transition relation of a nondeterministic machine. Every cause can lead
+/// to any number of effects, which are tried in the order they were added.
+pub type TransitionRelation<V, S = State> = HashMap<(S, Option<V>), Vec<(S, Option<V>, Direction)>>;
+
+/// A single transition on a path through the computation tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathStep<V, S = State> {
+    pub state: S,
+    pub read: Option<V>,
+    pub effect: (S, Option<V>, Direction)
+}
+
+/// The result of exploring the computation tree of a nondeterministic machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Exploration<V, S = State> {
+    /// A branch has reached an accepting state. The path contains the
+    /// transitions that lead there from the start, it is one of the shortest.
+    Accepted {
+        path: Vec<PathStep<V, S>>
+    },
+    /// Every branch has halted without accepting or got stuck.
+    Rejected,
+    /// Some branches had to be cut off because of the limits and none of the
+    /// explored ones has accepted. The cut off branches may still accept.
+    LimitReached,
+    /// Some branches could not perform a transition on the tape and none of
+    /// the explored ones has accepted. This is the first error found.
+    TapeError(TapeError<V>)
+}
+
+/// Bounds for the exploration of a computation tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExplorationLimits {
+    /// Number of transitions a single branch may perform
+    pub max_depth: usize,
+    /// Number of branches waiting to be explored at the same time. Further
+    /// branches are cut off.
+    pub max_branches: usize
+}
+
+/// A branch of the computation tree, that still needs to be explored.
+struct Branch<V: Tapeable, S> {
+    state: S,
+    tape: Box<dyn SimpleTape<V>>,
+    /// Index of the last path step leading to this branch, None for the root
+    node: Option<usize>,
+    depth: usize
+}
+
+/// Nondeterministic turing machine with one tape. It accepts, if any branch of
+/// its computation tree reaches an accepting state.
+pub struct NondeterministicMachine<V: Tapeable, S: Stateable = State> {
+    starting_state: S,
+    tape: Box<dyn SimpleTape<V>>,
+    transitions: TransitionRelation<V, S>,
+    final_states: FinalStates<S>
+}
+
+impl<V: Tapeable, S: Stateable + Default> NondeterministicMachine<V, S> {
+    /// Create a new machine with the input tape and an empty transition
+    /// relation. It starts in the default state, which is 0 for numbered
+    /// states.
+    pub fn new(tape: Box<dyn SimpleTape<V>>) -> NondeterministicMachine<V, S> {
+        NondeterministicMachine::with_starting_state(tape, S::default())
+    }
+}
+
+impl<V: Tapeable, S: Stateable> NondeterministicMachine<V, S> {
+    /// Create a new machine with the input tape, an empty transition relation
+    /// and the given starting state.
+    pub fn with_starting_state(tape: Box<dyn SimpleTape<V>>, starting_state: S) -> NondeterministicMachine<V, S> {
+        NondeterministicMachine {
+            starting_state,
+            tape,
+            transitions: HashMap::new(),
+            final_states: FinalStates::default()
+        }
+    }
+
+    /// Add an effect to the cause in the transition relation. Returns false, if
+    /// the cause already had this exact effect.
+    pub fn add_transition(&mut self, cause: (S, Option<V>), effect: (S, Option<V>, Direction)) -> bool {
+        let effects = self.transitions.entry(cause).or_default();
+        if effects.contains(&effect) {
+            false
+        }
+        else {
+            effects.push(effect);
+            true
+        }
+    }
+
+    /// Replace the input the computation tree is explored on. If the input
+    /// contains symbols outside of the input alphabet of the tape, an error is
+    /// returned and the input is left unchanged.
+    pub fn load_input(&mut self, input: &[Option<V>]) -> Result<(), TapeError<V>> {
+        self.tape.try_load(input)
+    }
+
+    pub fn tape(&self) -> &dyn SimpleTape<V> {
+        self.tape.deref()
+    }
+
+    /// Explore the computation tree breadth first, until a branch accepts or
+    /// every branch has halted, got stuck or has been cut off by the limits.
+    pub fn explore(&self, limits: ExplorationLimits) -> Exploration<V, S> {
+        // All path steps that have been taken, each pointing to the step before
+        let mut nodes: Vec<(Option<usize>, PathStep<V, S>)> = Vec::new();
+        let mut frontier = VecDeque::new();
+        frontier.push_back(Branch {
+            state: self.starting_state.clone(),
+            tape: self.tape.boxed_clone(),
+            node: None,
+            depth: 0
+        });
+        let mut cut_off = false;
+        let mut tape_error = None;
+
+        while let Some(branch) = frontier.pop_front() {
+            match self.final_states.verdict(&branch.state) {
+                Some(Verdict::Accept) => return Exploration::Accepted { path: Self::path_to(&nodes, branch.node) },
+                Some(_) => continue,
+                None => {}
+            }
+            if branch.depth >= limits.max_depth {
+                cut_off = true;
+                continue;
+            }
+
+            let read = branch.tape.read();
+            let effects = match self.transitions.get(&(branch.state.clone(), read)) {
+                Some(effects) => effects,
+                None => continue
+            };
+
+            for effect in effects {
+                if frontier.len() >= limits.max_branches {
+                    cut_off = true;
+                    break;
+                }
+
+                // A branch the tape refuses gets stuck like one without a
+                // transition, but the error is reported if nothing accepts
+                let mut tape = branch.tape.boxed_clone();
+                if let Err(err) = tape.try_write(effect.1).and_then(|_| tape.try_mv(effect.2)) {
+                    tape_error.get_or_insert(err);
+                    continue;
+                }
+
+                nodes.push((branch.node, PathStep { state: branch.state.clone(), read, effect: effect.clone() }));
+                frontier.push_back(Branch {
+                    state: effect.0.clone(),
+                    tape,
+                    node: Some(nodes.len() - 1),
+                    depth: branch.depth + 1
+                });
+            }
+        }
+
+        match tape_error {
+            Some(err) => Exploration::TapeError(err),
+            None if cut_off => Exploration::LimitReached,
+            None => Exploration::Rejected
+        }
+    }
+
+    /// Follow the path steps back from the node to the root of the tree.
+    fn path_to(nodes: &[(Option<usize>, PathStep<V, S>)], mut node: Option<usize>) -> Vec<PathStep<V, S>> {
+        let mut path = Vec::new();
+        while let Some(i) = node {
+            path.push(nodes[i].1.clone());
+            node = nodes[i].0;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Reaching a final state ends the branch that reached it.
+impl<V: Tapeable, S: Stateable> Halting<S> for NondeterministicMachine<V, S> {
+    fn final_states(&self) -> &FinalStates<S> {
+        &self.final_states
+    }
+
+    fn final_states_mut(&mut self) -> &mut FinalStates<S> {
+        &mut self.final_states
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::{Alphabet, Boundary, Tape};
+
+    /// Machine that guesses where the substring bb starts
+    fn contains_bb() -> NondeterministicMachine<char> {
+        let mut tm = NondeterministicMachine::new(Box::new(Tape::new()));
+        tm.add_transition((0, Some('a')), (0, Some('a'), Direction::Right));
+        tm.add_transition((0, Some('b')), (0, Some('b'), Direction::Right));
+        tm.add_transition((0, Some('b')), (1, Some('b'), Direction::Right));
+        tm.add_transition((1, Some('b')), (2, Some('b'), Direction::Right));
+        tm.add_accepting_state(2);
+        tm
+    }
+
+    fn input(s: &str) -> Vec<Option<char>> {
+        s.chars().map(Some).collect()
+    }
+
+    #[test]
+    fn test_explore() {
+        let limits = ExplorationLimits { max_depth: 100, max_branches: 100 };
+        let mut tm = contains_bb();
+
+        assert_eq!(tm.load_input(&input("abaabba")), Ok(()));
+        let path = match tm.explore(limits) {
+            Exploration::Accepted { path } => path,
+            other => panic!("Expected the input to be accepted, got {:?}", other)
+        };
+        assert_eq!(path.len(), 6);
+        assert_eq!(path[4], PathStep { state: 0, read: Some('b'), effect: (1, Some('b'), Direction::Right) });
+        assert_eq!(path[5].effect.0, 2);
+
+        assert_eq!(tm.load_input(&input("ababa")), Ok(()));
+        assert_eq!(tm.explore(limits), Exploration::Rejected);
+
+        // The accepting branch is deeper than allowed
+        assert_eq!(tm.load_input(&input("aaaabb")), Ok(()));
+        assert_eq!(tm.explore(ExplorationLimits { max_depth: 5, max_branches: 100 }), Exploration::LimitReached);
+    }
+
+    #[test]
+    fn test_bounded_tape() {
+        let limits = ExplorationLimits { max_depth: 100, max_branches: 100 };
+        let mut tape = Tape::linear_bounded(vec![Some('a')], '<', '>', Boundary::Error);
+        tape.set_alphabet(Some(Alphabet::new(['a'], ['<', '>'], None)));
+        let mut tm = NondeterministicMachine::new(Box::new(tape));
+        tm.add_transition((0, Some('a')), (0, Some('a'), Direction::Right));
+        tm.add_transition((0, Some('>')), (0, Some('>'), Direction::Right));
+        assert_eq!(tm.explore(limits), Exploration::TapeError(TapeError::OutOfBounds { position: 2 }));
+
+        // Another branch accepting wins over the error
+        tm.add_transition((0, Some('>')), (1, Some('>'), Direction::Hold));
+        tm.add_accepting_state(1);
+        assert!(matches!(tm.explore(limits), Exploration::Accepted { .. }));
+
+        // The end markers are not part of the input alphabet
+        let err = TapeError::SymbolOutsideAlphabet { symbol: Some('>'), position: 1 };
+        assert_eq!(tm.load_input(&[Some('a'), Some('>')]), Err(err));
+    }
+}
@@ -0,0 +1,106 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::tape::Direction;
+use crate::turing_machine::{State, StepOutcome, Verdict};
+
+/// A transition that has been performed by a turing machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionEvent<V, S = State> {
+    /// Number of the step, counted from 1 since the last reset
+    pub step: usize,
+    pub from_state: S,
+    pub read: Option<V>,
+    pub written: Option<V>,
+    pub direction: Direction,
+    pub to_state: S,
+    /// Position of the head, where the symbol has been read and written
+    pub head_position: isize,
+    /// Position of the head after it has been moved
+    pub new_head_position: isize
+}
+
+/// Receives the events happening inside a turing machine while it is running.
+/// All callbacks do nothing by default, so only the interesting ones need to
+/// be implemented.
+pub trait Observer<V, S = State> {
+    /// Called after every transition the machine has performed.
+    fn on_transition(&mut self, _event: &TransitionEvent<V, S>) {}
+
+    /// Called when the machine halts in the given state.
+    fn on_halt(&mut self, _verdict: Verdict, _state: &S) {}
+
+    /// Called when the machine cannot perform a step, with the outcome that is
+    /// returned from it.
+    fn on_error(&mut self, _outcome: &StepOutcome<V, S>) {}
+}
+
+/// Shared observers can be registered on a machine, while still being
+/// accessible from the outside to look at what they have collected.
+impl<V, S, O: Observer<V, S>> Observer<V, S> for Rc<RefCell<O>> {
+    fn on_transition(&mut self, event: &TransitionEvent<V, S>) {
+        self.borrow_mut().on_transition(event);
+    }
+
+    fn on_halt(&mut self, verdict: Verdict, state: &S) {
+        self.borrow_mut().on_halt(verdict, state);
+    }
+
+    fn on_error(&mut self, outcome: &StepOutcome<V, S>) {
+        self.borrow_mut().on_error(outcome);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::Tape;
+    use crate::turing_machine::{Halting, TuringMachine};
+
+    #[derive(Default)]
+    struct Recorder {
+        transitions: Vec<TransitionEvent<bool>>,
+        halts: Vec<(Verdict, State)>,
+        errors: usize
+    }
+
+    impl Observer<bool> for Recorder {
+        fn on_transition(&mut self, event: &TransitionEvent<bool>) {
+            self.transitions.push(event.clone());
+        }
+
+        fn on_halt(&mut self, verdict: Verdict, state: &State) {
+            self.halts.push((verdict, *state));
+        }
+
+        fn on_error(&mut self, _outcome: &StepOutcome<bool>) {
+            self.errors += 1;
+        }
+    }
+
+    #[test]
+    fn test_observer_events() {
+        let mut tm = TuringMachine::new(Box::new(Tape::tape(vec![Some(true)])));
+        tm.add_transition((0, Some(true)), (0, Some(false), Direction::Right));
+        tm.add_transition((0, None), (1, None, Direction::Hold));
+        tm.add_accepting_state(1);
+
+        let recorder = Rc::new(RefCell::new(Recorder::default()));
+        tm.add_observer(Box::new(recorder.clone()));
+        tm.run(10, None);
+
+        let recorder = recorder.borrow();
+        assert_eq!(recorder.transitions.len(), 2);
+        assert_eq!(recorder.transitions[0], TransitionEvent {
+            step: 1,
+            from_state: 0,
+            read: Some(true),
+            written: Some(false),
+            direction: Direction::Right,
+            to_state: 0,
+            head_position: 0,
+            new_head_position: 1
+        });
+        assert_eq!(recorder.halts, vec![(Verdict::Accept, 1)]);
+        assert_eq!(recorder.errors, 0);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::multi_tape::{MultiStepOutcome, MultiTapeMachine, MultiTransitionable};
+use crate::tape::{SimpleTape, TapeError, Tapeable};
+use crate::turing_machine::{State, Stateable, Verdict};
+
+/// Decides membership of the words written on the query tape of an oracle
+/// machine.
+pub trait Oracle<V> {
+    /// Answer the query, which is the content of the query tape without leading
+    /// and trailing blanks.
+    fn query(&mut self, query: &[Option<V>]) -> bool;
+}
+
+/// Any closure deciding a query can be used as an oracle.
+impl<V, F: FnMut(&[Option<V>]) -> bool> Oracle<V> for F {
+    fn query(&mut self, query: &[Option<V>]) -> bool {
+        self(query)
+    }
+}
+
+/// The special states of an oracle machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OracleStates<S = State> {
+    /// Entering this state asks the oracle about the query tape
+    pub query: S,
+    /// The state the machine continues in, if the oracle answered yes
+    pub yes: S,
+    /// The state the machine continues in, if the oracle answered no
+    pub no: S
+}
+
+/// Turing machine with a work tape and a query tape. Whenever the machine
+/// enters the query state, the oracle is asked about the contents of the query
+/// tape and the machine moves to the yes or no state in a single step. The
+/// query tape is erased afterwards. All other steps are ordinary transitions of
+/// a machine with two tapes, where tape 0 is the work tape and tape 1 is the
+/// query tape.
+pub struct OracleMachine<V: Tapeable, S: Stateable = State> {
+    machine: MultiTapeMachine<V, S>,
+    states: OracleStates<S>,
+    oracle: Box<dyn Oracle<V>>,
+    /// Number of times the oracle has been asked since the last reset
+    queries: usize
+}
+
+impl<V: Tapeable, S: Stateable> OracleMachine<V, S> {
+    /// Create an oracle machine from a machine with exactly two tapes. Its
+    /// transitions are used for every step that is not a query.
+    ///
+    /// # Panics
+    /// If the machine does not have two tapes.
+    pub fn new(machine: MultiTapeMachine<V, S>, states: OracleStates<S>, oracle: Box<dyn Oracle<V>>) -> OracleMachine<V, S> {
+        assert_eq!(machine.tape_count(), 2, "An oracle machine needs a work tape and a query tape");
+        OracleMachine {
+            machine,
+            states,
+            oracle,
+            queries: 0
+        }
+    }
+
+    /// Replace the oracle, for example to run the same machine relative to a
+    /// different language.
+    pub fn set_oracle(&mut self, oracle: Box<dyn Oracle<V>>) {
+        self.oracle = oracle;
+    }
+
+    /// Load the input onto the work tape, clear the query tape and reset the
+    /// machine. If the input contains symbols outside of the input alphabet of
+    /// the work tape, an error is returned and the machine is left unchanged.
+    pub fn load_input(&mut self, input: &[Option<V>]) -> Result<(), TapeError<V>> {
+        self.machine.load_input(input)?;
+        self.queries = 0;
+        Ok(())
+    }
+
+    /// Reset the machine to its starting state and restore the tapes.
+    pub fn reset(&mut self) {
+        self.machine.reset();
+        self.queries = 0;
+    }
+
+    pub fn current_state(&self) -> &S {
+        self.machine.current_state()
+    }
+
+    pub fn steps(&self) -> usize {
+        self.machine.steps()
+    }
+
+    /// The number of times the oracle has been asked since the last reset.
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    pub fn work_tape(&self) -> &dyn SimpleTape<V> {
+        self.machine.tape(0)
+    }
+
+    pub fn query_tape(&self) -> &dyn SimpleTape<V> {
+        self.machine.tape(1)
+    }
+
+    /// The machine the oracle machine is built on, e.g. to add transitions.
+    pub fn machine(&mut self) -> &mut MultiTapeMachine<V, S> {
+        &mut self.machine
+    }
+}
+
+impl<V: Tapeable, S: Stateable> MultiTransitionable<V, S> for OracleMachine<V, S> {
+    fn verdict(&self) -> Option<Verdict> {
+        self.machine.verdict()
+    }
+
+    /// Perform the next step. In the query state this asks the oracle,
+    /// otherwise the next transition is performed.
+    fn step(&mut self) -> MultiStepOutcome<V, S> {
+        if self.machine.current_state() != &self.states.query {
+            return self.machine.step();
+        }
+
+        let query = self.machine.tape(1).contents_trim_blanks();
+        self.queries += 1;
+        let answer = if self.oracle.query(&query) { self.states.yes.clone() } else { self.states.no.clone() };
+        self.machine.tape_mut(1).load(&[]);
+        self.machine.enter_state(answer);
+
+        match self.verdict() {
+            Some(verdict) => MultiStepOutcome::Halted(verdict),
+            None => MultiStepOutcome::Continued
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::multi_tape::MultiRunOutcome;
+    use crate::tape::{Direction::*, Tape};
+    use crate::turing_machine::Halting;
+
+    /// Copies the input onto the query tape and decides the complement of the
+    /// oracle language.
+    fn complement(oracle: Box<dyn Oracle<char>>) -> OracleMachine<char, &'static str> {
+        let mut tm = MultiTapeMachine::with_starting_state(vec![Box::new(Tape::new()), Box::new(Tape::new())], "copy");
+        for a in ['a', 'b'] {
+            tm.add_transition(("copy", vec![Some(a), None]), ("copy", vec![Some(a), Some(a)], vec![Right, Right]));
+        }
+        tm.add_transition(("copy", vec![None, None]), ("query", vec![None, None], vec![Hold, Hold]));
+        tm.add_accepting_state("no");
+        tm.add_rejecting_state("yes");
+
+        OracleMachine::new(tm, OracleStates { query: "query", yes: "yes", no: "no" }, oracle)
+    }
+
+    #[test]
+    fn test_oracle() {
+        let even = |query: &[Option<char>]| query.len().is_multiple_of(2);
+        let mut tm = complement(Box::new(even));
+
+        assert_eq!(tm.load_input(&[Some('a'), Some('b'), Some('a')]), Ok(()));
+        assert_eq!(tm.run(100), MultiRunOutcome::Halted(Verdict::Accept));
+        assert_eq!(tm.steps(), 5);
+        assert_eq!(tm.queries(), 1);
+        assert_eq!(tm.query_tape().contents_trim_blanks(), vec![]);
+
+        assert_eq!(tm.load_input(&[Some('a'), Some('b')]), Ok(()));
+        assert_eq!(tm.run(100), MultiRunOutcome::Halted(Verdict::Reject));
+
+        // Relative to another oracle, the same machine decides another language
+        tm.set_oracle(Box::new(|query: &[Option<char>]| query.contains(&Some('b'))));
+        tm.reset();
+        assert_eq!(tm.run(100), MultiRunOutcome::Halted(Verdict::Reject));
+    }
+}
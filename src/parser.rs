@@ -1,4 +1,4 @@
-use crate::turing_machine::{TuringMachine, State};
+use crate::turing_machine::{Stateable, TuringMachine};
 use crate::tape::{Direction, Tape, Tapeable};
 use crate::lexicaliser::*;
 
@@ -36,7 +36,8 @@ pub enum ParseError {
     /// result in this kind of error
     WrongNumberOfArguments,
     /// Encountered, when the String has an unaccepted type at a certain point,
-    /// like a float, where an uint was expected
+    /// like a float, where an uint was expected, or a name, where only numbered
+    /// states are expected
     InvalidType,
     /// If an implication is pointing towards nothing, this error is thrown
     ImplyingNothing,
@@ -51,11 +52,12 @@ pub enum ParseError {
 }
 
 /// Parse a String to create a simple DTM with one tape, expects the alphabet
-/// to be used in the TM.
-pub fn parse_simple_turing_machine<S: AsRef<str>, G>(src: S) -> Result<TuringMachine<G>, ParseError>
-        where <G as FromStr>::Err: fmt::Debug, G: Tapeable + FromStr + 'static {
+/// and the type of the states to be used in the TM. States can be numbered,
+/// or be given names like q_accept, when parsed as Strings.
+pub fn parse_simple_turing_machine<S: AsRef<str>, G, Q>(src: S) -> Result<TuringMachine<G, Q>, ParseError>
+        where <G as FromStr>::Err: fmt::Debug, G: Tapeable + FromStr + 'static, Q: Stateable + FromStr + Default {
     // Set to the default starting state and create an empty transition table
-    let mut starting_state = 0;
+    let mut starting_state = Q::default();
     let mut transitions = HashMap::new();
 
     let lexed = match lexicalise(&src) {
@@ -87,7 +89,7 @@ pub fn parse_simple_turing_machine<S: AsRef<str>, G>(src: S) -> Result<TuringMac
         };
 
         // Check if it is the starting state in form () -> (q0)
-        if cause.len() == 0 && effect.len() == 1 {
+        if cause.is_empty() && effect.len() == 1 {
             if effect[0].is_none() {
                 return Err(ParseError::MustHaveStartingState);
             }
@@ -109,7 +111,7 @@ pub fn parse_simple_turing_machine<S: AsRef<str>, G>(src: S) -> Result<TuringMac
                 }
             };
 
-            let q: State = parse_state(cause[0].clone())?;
+            let q: Q = parse_state(cause[0].clone())?;
             let a = if let Some(a) = cause[1].clone() {
                 match a.parse() {
                     Ok(a) => Some(a),
@@ -146,7 +148,7 @@ pub fn parse_simple_turing_machine<S: AsRef<str>, G>(src: S) -> Result<TuringMac
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::turing_machine::Transitionable;
+    use crate::turing_machine::{Halting, HaltingPolicy, RunOutcome, Verdict};
 
     #[test]
     fn test_simple_tm_parse() {
@@ -157,16 +159,37 @@ mod tests {
         (1, None) -> (1, None, Hold)
         ".to_string();
         let tape = Tape::tape(vec![Some(true), Some(false), Some(true), Some(false), Some(true), Some(false)]);
-        let mut tm = parse_simple_turing_machine(&source).expect("Could not parse turing machine");
+        let mut tm: TuringMachine<bool> = parse_simple_turing_machine(&source).expect("Could not parse turing machine");
         tm.insert_tape(Box::new(tape));
+        // The source ends in a self loop instead of declaring a halting state
+        tm.set_halting_policy(HaltingPolicy::SelfLoop);
 
-        while tm.step() {
-            println!("Stepping TM");
-        }
+        let report = tm.run(1000, None);
+        assert_eq!(report.outcome, RunOutcome::Halted(Verdict::Halt));
 
         println!("TM finished:");
         tm.print();
 
         assert_eq!(tm.tape().contents_trim_blanks(), vec![Some(false), Some(true), Some(false), Some(true), Some(false), Some(true)]);
     }
+
+    #[test]
+    fn test_named_states_parse() {
+        let source = "
+        () -> (scan_right)
+        (scan_right, true) -> (scan_right, true, Right)
+        (scan_right, None) -> (q_accept, None, Hold)
+        ";
+        let mut tm: TuringMachine<bool, String> = parse_simple_turing_machine(source).expect("Could not parse turing machine");
+        tm.add_accepting_state("q_accept".to_string());
+        assert_eq!(tm.load_input(&[Some(true), Some(true)]), Ok(()));
+
+        let report = tm.run(1000, None);
+        assert_eq!(report.outcome, RunOutcome::Halted(Verdict::Accept));
+        assert_eq!(report.final_state, "q_accept");
+
+        // Names cannot be used, when numbered states are expected
+        let numbered: Result<TuringMachine<bool>, _> = parse_simple_turing_machine(source);
+        assert!(matches!(numbered, Err(ParseError::InvalidType)));
+    }
 }
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use crate::tape::{Direction, SimpleTape, TapeError, Tapeable};
+use crate::turing_machine::{FinalStates, Halting, RunOutcome, State, Stateable, Verdict};
+
+/// The transitions of a probabilistic machine. Every cause leads to a list of
+/// effects, each with a positive weight. An effect is chosen with probability
+/// proportional to its weight.
+pub type WeightedTransitions<V, S = State> = HashMap<(S, Option<V>), Vec<WeightedEffect<V, S>>>;
+
+/// An effect together with its weight.
+pub type WeightedEffect<V, S = State> = (f64, (S, Option<V>, Direction));
+
+/// The z value for a confidence level of 95%.
+pub const Z_95: f64 = 1.96;
+
+/// Small, seedable random number generator (SplitMix64), so that runs can be
+/// reproduced exactly from their seed.
+#[derive(Debug, Clone)]
+struct SeededRng {
+    state: u64
+}
+
+impl SeededRng {
+    fn new(seed: u64) -> SeededRng {
+        SeededRng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed number in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Result of estimating the probability that a machine accepts its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AcceptanceEstimate {
+    pub runs: usize,
+    pub accepted: usize,
+    /// Runs that did not halt within the step limit. They count as not
+    /// accepted.
+    pub undecided: usize
+}
+
+impl AcceptanceEstimate {
+    /// The fraction of runs that have accepted.
+    pub fn probability(&self) -> f64 {
+        if self.runs == 0 { 0.0 } else { self.accepted as f64 / self.runs as f64 }
+    }
+
+    /// Wilson score interval for the acceptance probability. z selects the
+    /// confidence level, for instance Z_95 for 95%.
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        if self.runs == 0 {
+            return (0.0, 1.0);
+        }
+
+        let n = self.runs as f64;
+        let p = self.probability();
+        let denominator = 1.0 + z * z / n;
+        let centre = (p + z * z / (2.0 * n)) / denominator;
+        let spread = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+
+        ((centre - spread).max(0.0), (centre + spread).min(1.0))
+    }
+}
+
+/// Turing machine with one tape, that chooses between its transitions at
+/// random.
+pub struct ProbabilisticMachine<V: Tapeable, S: Stateable = State> {
+    starting_state: S,
+    tape: Box<dyn SimpleTape<V>>,
+    transitions: WeightedTransitions<V, S>,
+    final_states: FinalStates<S>
+}
+
+impl<V: Tapeable, S: Stateable + Default> ProbabilisticMachine<V, S> {
+    /// Create a new machine with the input tape and no transitions. It starts
+    /// in the default state, which is 0 for numbered states.
+    pub fn new(tape: Box<dyn SimpleTape<V>>) -> ProbabilisticMachine<V, S> {
+        ProbabilisticMachine::with_starting_state(tape, S::default())
+    }
+}
+
+impl<V: Tapeable, S: Stateable> ProbabilisticMachine<V, S> {
+    /// Create a new machine with the input tape, no transitions and the given
+    /// starting state.
+    pub fn with_starting_state(tape: Box<dyn SimpleTape<V>>, starting_state: S) -> ProbabilisticMachine<V, S> {
+        ProbabilisticMachine {
+            starting_state,
+            tape,
+            transitions: HashMap::new(),
+            final_states: FinalStates::default()
+        }
+    }
+
+    /// Add a possible effect with the given weight to the cause. Weights that
+    /// are not positive are ignored.
+    pub fn add_transition(&mut self, cause: (S, Option<V>), weight: f64, effect: (S, Option<V>, Direction)) {
+        if weight > 0.0 {
+            self.transitions.entry(cause).or_default().push((weight, effect));
+        }
+    }
+
+    /// Replace the input the machine is run on. If the input contains symbols
+    /// outside of the input alphabet of the tape, an error is returned and the
+    /// input is left unchanged.
+    pub fn load_input(&mut self, input: &[Option<V>]) -> Result<(), TapeError<V>> {
+        self.tape.try_load(input)
+    }
+
+    pub fn tape(&self) -> &dyn SimpleTape<V> {
+        self.tape.deref()
+    }
+
+    /// Run the machine once on a copy of its input, with the random choices
+    /// determined by the seed. Performs at most max_steps transitions.
+    pub fn run(&self, seed: u64, max_steps: usize) -> RunOutcome<V, S> {
+        let mut rng = SeededRng::new(seed);
+        let mut state = self.starting_state.clone();
+        let mut tape = self.tape.boxed_clone();
+
+        for _ in 0..max_steps {
+            if let Some(verdict) = self.final_states.verdict(&state) {
+                return RunOutcome::Halted(verdict);
+            }
+
+            let symbol = tape.read();
+            let effects = match self.transitions.get(&(state.clone(), symbol)) {
+                Some(effects) => effects,
+                None => return RunOutcome::NoTransition { state, symbol, head_position: tape.head_position() }
+            };
+
+            let (new_state, value, direction) = Self::choose(&mut rng, effects);
+            if let Err(err) = tape.try_write(*value).and_then(|_| tape.try_mv(*direction)) {
+                return RunOutcome::TapeError(err);
+            }
+            state = new_state.clone();
+        }
+
+        match self.final_states.verdict(&state) {
+            Some(verdict) => RunOutcome::Halted(verdict),
+            None => RunOutcome::StepLimitReached
+        }
+    }
+
+    /// Estimate the probability of acceptance by running the machine the given
+    /// number of times. The seeds of the single runs are derived from the
+    /// seed, so the estimate is reproducible.
+    pub fn estimate_acceptance(&self, runs: usize, seed: u64, max_steps: usize) -> AcceptanceEstimate {
+        let mut seeds = SeededRng::new(seed);
+        let mut estimate = AcceptanceEstimate { runs, accepted: 0, undecided: 0 };
+
+        for _ in 0..runs {
+            match self.run(seeds.next_u64(), max_steps) {
+                RunOutcome::Halted(Verdict::Accept) => estimate.accepted += 1,
+                RunOutcome::StepLimitReached => estimate.undecided += 1,
+                _ => {}
+            }
+        }
+
+        estimate
+    }
+
+    /// Pick one of the effects with probability proportional to its weight.
+    fn choose<'a>(rng: &mut SeededRng, effects: &'a [WeightedEffect<V, S>]) -> &'a (S, Option<V>, Direction) {
+        let total: f64 = effects.iter().map(|(w, _)| w).sum();
+        let mut target = rng.next_f64() * total;
+
+        for (weight, effect) in effects {
+            if target < *weight {
+                return effect;
+            }
+            target -= weight;
+        }
+
+        // Only reachable through rounding errors
+        &effects[effects.len() - 1].1
+    }
+}
+
+impl<V: Tapeable, S: Stateable> Halting<S> for ProbabilisticMachine<V, S> {
+    fn final_states(&self) -> &FinalStates<S> {
+        &self.final_states
+    }
+
+    fn final_states_mut(&mut self) -> &mut FinalStates<S> {
+        &mut self.final_states
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::{Boundary, Tape};
+
+    #[test]
+    fn test_estimate_acceptance() {
+        // Flips a biased coin once, accepting with a probability of 3/4
+        let mut tm = ProbabilisticMachine::new(Box::new(Tape::<bool>::new()));
+        tm.add_transition((0, None), 3.0, (1, None, Direction::Hold));
+        tm.add_transition((0, None), 1.0, (2, None, Direction::Hold));
+        tm.add_accepting_state(1);
+        tm.add_rejecting_state(2);
+
+        let estimate = tm.estimate_acceptance(2000, 42, 10);
+        assert_eq!(estimate, tm.estimate_acceptance(2000, 42, 10));
+        assert_eq!(estimate.undecided, 0);
+
+        let (low, high) = estimate.confidence_interval(Z_95);
+        assert!(low < 0.75 && 0.75 < high, "{} not in ({}, {})", 0.75, low, high);
+        assert!(high - low < 0.05);
+        assert!(low <= estimate.probability() && estimate.probability() <= high);
+
+        // Same seed, same result
+        assert_eq!(tm.run(7, 10), tm.run(7, 10));
+    }
+
+    #[test]
+    fn test_bounded_tape() {
+        let mut tm = ProbabilisticMachine::new(Box::new(Tape::linear_bounded(Vec::new(), '<', '>', Boundary::Error)));
+        tm.add_transition((0, Some('>')), 1.0, (0, Some('>'), Direction::Right));
+        assert_eq!(tm.run(1, 10), RunOutcome::TapeError(TapeError::OutOfBounds { position: 1 }));
+    }
+}
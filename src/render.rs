@@ -0,0 +1,144 @@
+use std::ops::Range;
+use crate::tape::{SimpleTape, Tapeable};
+use crate::turing_machine::{Stateable, TuringMachine};
+
+/// How the position of the head is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadMarker {
+    /// The head is not shown at all.
+    Hidden,
+    /// The cell under the head is enclosed in brackets, e.g. a [b] c
+    Brackets,
+    /// A second line is added, with a caret below the cell under the head.
+    Caret
+}
+
+/// Renders tapes and machines as text. The output only depends on the options
+/// and the contents, so it can be compared against golden files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renderer {
+    /// Printed for blank cells
+    pub blank: String,
+    /// Printed between two neighbouring cells
+    pub separator: String,
+    pub head_marker: HeadMarker,
+    /// Only print the cells up to this distance from the head. Otherwise all
+    /// stored cells and the one under the head are printed.
+    pub window: Option<usize>,
+    /// Print the current state in front of the tape, when rendering a machine
+    pub show_state: bool
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer {
+            blank: "_".to_string(),
+            separator: " ".to_string(),
+            head_marker: HeadMarker::Hidden,
+            window: None,
+            show_state: false
+        }
+    }
+}
+
+impl Renderer {
+    /// Render the cells of the tape.
+    pub fn render_tape<V: Tapeable>(&self, tape: &dyn SimpleTape<V>) -> String {
+        self.render_line(String::new(), tape)
+    }
+
+    /// Render the tape of the machine, and its state if show_state is set.
+    pub fn render<V: Tapeable, S: Stateable>(&self, tm: &TuringMachine<V, S>) -> String {
+        let prefix = if self.show_state { format!("{}: ", tm.current_state()) } else { String::new() };
+        self.render_line(prefix, tm.tape())
+    }
+
+    /// The positions of the cells that will be printed.
+    fn positions<V: Tapeable>(&self, tape: &dyn SimpleTape<V>) -> Range<isize> {
+        let head = tape.head_position();
+        match self.window {
+            Some(radius) => head - radius as isize..head + radius as isize + 1,
+            None => {
+                let extent = tape.extent();
+                extent.start.min(head)..extent.end.max(head + 1)
+            }
+        }
+    }
+
+    fn render_line<V: Tapeable>(&self, mut line: String, tape: &dyn SimpleTape<V>) -> String {
+        let blank = tape.alphabet().and_then(|a| a.blank());
+        let head = tape.head_position();
+        let mut caret = None;
+
+        for (n, position) in self.positions(tape).enumerate() {
+            if n > 0 {
+                line.push_str(&self.separator);
+            }
+
+            let glyph = match tape.cell(position) {
+                Some(v) if Some(v) != blank => v.to_string(),
+                _ => self.blank.clone()
+            };
+            if position != head {
+                line.push_str(&glyph);
+                continue;
+            }
+
+            match self.head_marker {
+                HeadMarker::Hidden => line.push_str(&glyph),
+                HeadMarker::Brackets => line.push_str(&format!("[{}]", glyph)),
+                HeadMarker::Caret => {
+                    caret = Some(line.chars().count());
+                    line.push_str(&glyph);
+                }
+            }
+        }
+
+        if let Some(column) = caret {
+            line.push('\n');
+            line.push_str(&" ".repeat(column));
+            line.push('^');
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::{Direction, Tape};
+    use crate::turing_machine::Halting;
+
+    #[test]
+    fn test_render_tape() {
+        let mut tape = Tape::tape(vec![Some('a'), None, Some('c')]);
+        assert_eq!(tape.to_string(), "[ a _ c ]");
+
+        let brackets = Renderer { head_marker: HeadMarker::Brackets, ..Renderer::default() };
+        tape.mv_left();
+        assert_eq!(brackets.render_tape(&tape), "[_] a _ c");
+
+        let caret = Renderer {
+            blank: ".".to_string(),
+            separator: "|".to_string(),
+            head_marker: HeadMarker::Caret,
+            window: Some(1),
+            show_state: false
+        };
+        tape.mv_right();
+        tape.mv_right();
+        assert_eq!(caret.render_tape(&tape), "a|.|c\n  ^");
+    }
+
+    #[test]
+    fn test_render_machine() {
+        let mut tm = TuringMachine::with_starting_state(Box::new(Tape::tape(vec![Some(1), Some(2)])), "scan");
+        tm.add_transition(("scan", Some(1)), ("scan", Some(3), Direction::Right));
+        tm.add_transition(("scan", Some(2)), ("done", Some(4), Direction::Right));
+        tm.add_halting_state("done");
+        tm.run(10, None);
+
+        let renderer = Renderer { head_marker: HeadMarker::Brackets, show_state: true, window: Some(2), ..Renderer::default() };
+        assert_eq!(renderer.render(&tm), "done: 3 4 [_] _ _");
+    }
+}
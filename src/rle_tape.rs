@@ -0,0 +1,278 @@
+use std::fmt::{self, Display};
+use std::ops::Range;
+use crate::tape::{Alphabet, Direction, SimpleTape, Tapeable};
+
+/// A block of identical cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Run<V> {
+    start: isize,
+    len: usize,
+    value: Option<V>
+}
+
+impl<V> Run<V> {
+    fn end(&self) -> isize {
+        self.start + self.len as isize
+    }
+}
+
+/// Tape storing runs of identical symbols instead of single cells, so its
+/// memory use grows with the number of runs. Suited for machines like busy
+/// beaver candidates, which write long repeated blocks over millions of cells.
+#[derive(Clone)]
+pub struct RleTape<V: Tapeable> {
+    /// Runs covering the extent of the tape without gaps, ordered by their
+    /// start. Neighbouring runs never have the same value.
+    runs: Vec<Run<V>>,
+    head_position: isize,
+    alphabet: Option<Alphabet<V>>
+}
+
+impl<V: Tapeable> RleTape<V> {
+    /// Create a new, empty tape
+    pub fn new() -> RleTape<V> {
+        RleTape {
+            runs: Vec::new(),
+            head_position: 0,
+            alphabet: None
+        }
+    }
+
+    /// Create a tape from the values, with the head on the first one
+    pub fn tape(tape: Vec<Option<V>>) -> RleTape<V> {
+        let mut res = RleTape::new();
+        res.load(&tape);
+        res
+    }
+
+    /// The number of runs the tape is stored in.
+    pub fn run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    fn blank(&self) -> Option<V> {
+        self.alphabet.as_ref().and_then(|a| a.blank())
+    }
+
+    /// Index of the run containing the position, if it is inside the extent.
+    fn find(&self, position: isize) -> Option<usize> {
+        let i = self.runs.partition_point(|r| r.end() <= position);
+        self.runs.get(i).filter(|r| r.start <= position).map(|_| i)
+    }
+
+    // Extend the runs with blanks until they contain the position
+    fn cover(&mut self, position: isize) {
+        let (first, last) = match self.runs.first() {
+            None => {
+                self.runs.push(Run { start: position, len: 1, value: None });
+                return;
+            },
+            Some(run) => (run.start, self.runs.len() - 1)
+        };
+
+        if position < first {
+            let len = (first - position) as usize;
+            if self.runs[0].value.is_none() {
+                self.runs[0].start = position;
+                self.runs[0].len += len;
+            }
+            else {
+                self.runs.insert(0, Run { start: position, len, value: None });
+            }
+        }
+        else if position >= self.runs[last].end() {
+            let len = (position - self.runs[last].end() + 1) as usize;
+            if self.runs[last].value.is_none() {
+                self.runs[last].len += len;
+            }
+            else {
+                let start = self.runs[last].end();
+                self.runs.push(Run { start, len, value: None });
+            }
+        }
+    }
+
+    fn read_at(&self, position: isize) -> Option<V> {
+        self.find(position).and_then(|i| self.runs[i].value).or(self.blank())
+    }
+
+    fn write_at(&mut self, position: isize, val: Option<V>) {
+        // Blanks are stored as None, so they can always be merged
+        let val = if val == self.blank() { None } else { val };
+
+        self.cover(position);
+        let i = self.find(position).expect("Position has just been covered");
+        let run = self.runs[i];
+        if run.value == val {
+            return;
+        }
+
+        // Split the run around the position and merge the new cell with the
+        // neighbouring runs if they have the same value
+        let left = Run { start: run.start, len: (position - run.start) as usize, value: run.value };
+        let right = Run { start: position + 1, len: (run.end() - position - 1) as usize, value: run.value };
+        let mut cell = Run { start: position, len: 1, value: val };
+        let mut from = i;
+        let mut to = i + 1;
+        if left.len == 0 && i > 0 && self.runs[i - 1].value == val {
+            from -= 1;
+            cell.start = self.runs[from].start;
+            cell.len += self.runs[from].len;
+        }
+        if right.len == 0 && self.runs.get(i + 1).is_some_and(|r| r.value == val) {
+            cell.len += self.runs[i + 1].len;
+            to += 1;
+        }
+
+        let replacement = IntoIterator::into_iter([left, cell, right]).filter(|r| r.len > 0);
+        self.runs.splice(from..to, replacement);
+    }
+}
+
+impl<V: Tapeable> Default for RleTape<V> {
+    fn default() -> RleTape<V> {
+        RleTape::new()
+    }
+}
+
+impl<V: Tapeable> SimpleTape<V> for RleTape<V> {
+    fn mv(&mut self, direction: Direction) -> Option<V> {
+        self.head_position += direction as isize;
+        self.read()
+    }
+
+    fn mv_left(&mut self) -> Option<V> {
+        self.mv(Direction::Left)
+    }
+
+    fn mv_right(&mut self) -> Option<V> {
+        self.mv(Direction::Right)
+    }
+
+    fn read(&self) -> Option<V> {
+        self.read_at(self.head_position)
+    }
+
+    fn write(&mut self, val: Option<V>) {
+        self.write_at(self.head_position, val);
+    }
+
+    fn cell(&self, position: isize) -> Option<V> {
+        self.read_at(position)
+    }
+
+    fn head_position(&self) -> isize {
+        self.head_position
+    }
+
+    fn extent(&self) -> Range<isize> {
+        match (self.runs.first(), self.runs.last()) {
+            (Some(first), Some(last)) => first.start..last.end(),
+            _ => 0..0
+        }
+    }
+
+    fn load(&mut self, input: &[Option<V>]) {
+        self.runs.clear();
+        self.head_position = 0;
+        for (i, &v) in input.iter().enumerate() {
+            self.write_at(i as isize, v);
+        }
+    }
+
+    fn alphabet(&self) -> Option<&Alphabet<V>> {
+        self.alphabet.as_ref()
+    }
+
+    fn set_alphabet(&mut self, alphabet: Option<Alphabet<V>>) {
+        self.alphabet = alphabet;
+    }
+
+    fn boxed_clone(&self) -> Box<dyn SimpleTape<V>> {
+        Box::new(self.clone())
+    }
+
+    fn contents(&self) -> Vec<Option<V>> {
+        let blank = self.blank();
+        self.runs.iter()
+            .flat_map(|r| std::iter::repeat_n(r.value.or(blank), r.len))
+            .collect()
+    }
+
+    fn contents_trim_blanks(&self) -> Vec<Option<V>> {
+        let blank = self.blank();
+        let mut res = self.contents();
+        while res.last().is_some_and(|&x| x == blank) { res.pop(); }
+        let start = res.iter().position(|&x| x != blank).unwrap_or(res.len());
+        res.split_off(start)
+    }
+}
+
+/// Prints every run as its value and its length, e.g. [ 1x3 Nonex2 ]
+impl<V: Tapeable> Display for RleTape<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "[ ")?;
+        for run in &self.runs {
+            match run.value.or(self.blank()) {
+                Some(v) => write!(f, "{}x{} ", v, run.len)?,
+                None => write!(f, "Nonex{} ", run.len)?
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::Tape;
+    use crate::turing_machine::{Halting, RunOutcome, TuringMachine, Verdict};
+
+    #[test]
+    fn test_runs() {
+        let mut tape = RleTape::tape(vec![Some(1), Some(1), Some(2), Some(1)]);
+        assert_eq!(tape.run_count(), 3);
+
+        // Joining the blocks merges the runs
+        tape.mv_right();
+        tape.mv_right();
+        tape.write(Some(1));
+        assert_eq!(tape.run_count(), 1);
+        assert_eq!(tape.contents(), vec![Some(1); 4]);
+
+        for _ in 0..1000 {
+            tape.mv_right();
+            tape.write(Some(1));
+        }
+        tape.mv(Direction::Left);
+        tape.write(None);
+        assert_eq!(tape.run_count(), 3);
+        assert_eq!(tape.extent(), 0..1003);
+        assert_eq!(tape.cell(1001), None);
+        assert_eq!(tape.cell(-1), None);
+    }
+
+    #[test]
+    fn test_busy_beaver() {
+        // The three state busy beaver, run on both kinds of tapes
+        let machine = |tape: Box<dyn SimpleTape<bool>>| {
+            let mut tm = TuringMachine::new(tape);
+            tm.add_transition((0, None), (1, Some(true), Direction::Right));
+            tm.add_transition((0, Some(true)), (3, Some(true), Direction::Right));
+            tm.add_transition((1, None), (2, None, Direction::Right));
+            tm.add_transition((1, Some(true)), (1, Some(true), Direction::Right));
+            tm.add_transition((2, None), (2, Some(true), Direction::Left));
+            tm.add_transition((2, Some(true)), (0, Some(true), Direction::Left));
+            tm.add_halting_state(3);
+            tm
+        };
+
+        let mut rle = machine(Box::new(RleTape::tape(Vec::new())));
+        let mut tape = machine(Box::new(Tape::tape(Vec::new())));
+        assert_eq!(rle.run(100, None).outcome, RunOutcome::Halted(Verdict::Halt));
+        tape.run(100, None);
+        assert_eq!(rle.steps(), tape.steps());
+        assert_eq!(rle.tape().contents_trim_blanks(), tape.tape().contents_trim_blanks());
+        assert_eq!(rle.tape().head_position(), tape.tape().head_position());
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use crate::observer::{Observer, TransitionEvent};
+use crate::turing_machine::State;
+
+/// Statistics about the execution of a turing machine, filled in transition by
+/// transition.
+#[derive(Debug, Clone)]
+pub struct RunStats<V, S = State> {
+    /// Total number of transitions performed
+    pub steps: usize,
+    /// How often the transition for each cause (state, symbol read) has been
+    /// performed
+    pub transition_hits: HashMap<(S, Option<V>), usize>,
+    /// The leftmost position the head has been on
+    pub leftmost_cell: isize,
+    /// The rightmost position the head has been on
+    pub rightmost_cell: isize,
+    /// How often the head has changed between moving left and moving right.
+    /// Holding the head does not count as a change of direction.
+    pub head_reversals: usize,
+    /// The time spent in each state, measured in the number of transitions
+    /// that have been performed from it
+    pub steps_per_state: HashMap<S, usize>,
+    /// Positions of the cells whose symbol has been changed
+    cells_written: HashSet<isize>,
+    /// Direction of the last actual movement of the head, -1 or 1
+    last_move: Option<isize>
+}
+
+impl<V: Eq + Hash, S: Eq + Hash> Default for RunStats<V, S> {
+    fn default() -> RunStats<V, S> {
+        RunStats {
+            steps: 0,
+            transition_hits: HashMap::new(),
+            leftmost_cell: 0,
+            rightmost_cell: 0,
+            head_reversals: 0,
+            steps_per_state: HashMap::new(),
+            cells_written: HashSet::new(),
+            last_move: None
+        }
+    }
+}
+
+impl<V: Clone + Eq + Hash, S: Clone + Eq + Hash> RunStats<V, S> {
+    pub fn new() -> RunStats<V, S> {
+        RunStats::default()
+    }
+
+    /// Update the statistics with a transition that has been performed.
+    pub fn record(&mut self, event: &TransitionEvent<V, S>) {
+        if self.steps == 0 {
+            self.leftmost_cell = event.head_position;
+            self.rightmost_cell = event.head_position;
+        }
+        self.steps += 1;
+
+        *self.transition_hits.entry((event.from_state.clone(), event.read.clone())).or_insert(0) += 1;
+        *self.steps_per_state.entry(event.from_state.clone()).or_insert(0) += 1;
+        if event.read != event.written {
+            self.cells_written.insert(event.head_position);
+        }
+
+        self.leftmost_cell = self.leftmost_cell.min(event.new_head_position);
+        self.rightmost_cell = self.rightmost_cell.max(event.new_head_position);
+
+        let head_move = (event.new_head_position - event.head_position).signum();
+        if head_move != 0 {
+            if self.last_move.is_some_and(|last| last != head_move) {
+                self.head_reversals += 1;
+            }
+            self.last_move = Some(head_move);
+        }
+    }
+
+    /// The number of distinct cells that have been written to. Writing the
+    /// symbol a cell already contains does not count.
+    pub fn cells_written(&self) -> usize {
+        self.cells_written.len()
+    }
+}
+
+impl<V: Clone + Eq + Hash, S: Clone + Eq + Hash> Observer<V, S> for RunStats<V, S> {
+    fn on_transition(&mut self, event: &TransitionEvent<V, S>) {
+        self.record(event);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::tape::{Direction, Tape};
+    use crate::turing_machine::{Halting, TuringMachine};
+
+    #[test]
+    fn test_stats() {
+        // Walks to the end of the input, marks it and walks back to the start
+        let mut tm = TuringMachine::new(Box::new(Tape::tape(vec![Some(1), Some(1), Some(1)])));
+        tm.add_transition((0, Some(1)), (0, Some(1), Direction::Right));
+        tm.add_transition((0, None), (1, Some(2), Direction::Left));
+        tm.add_transition((1, Some(1)), (1, Some(1), Direction::Left));
+        tm.add_transition((1, None), (2, None, Direction::Right));
+        tm.add_halting_state(2);
+        tm.collect_stats(true);
+        // The same statistics can be gathered by an observer
+        let observed = Rc::new(RefCell::new(RunStats::new()));
+        tm.add_observer(Box::new(observed.clone()));
+        tm.run(100, None);
+
+        let stats = tm.stats().expect("Stats are collected");
+        assert_eq!(stats.steps, 8);
+        assert_eq!(stats.transition_hits[&(0, Some(1))], 3);
+        assert_eq!(stats.transition_hits[&(1, None)], 1);
+        assert_eq!((stats.leftmost_cell, stats.rightmost_cell), (-1, 3));
+        // Only the end marker changes a cell, everything else is rewritten
+        assert_eq!(stats.cells_written(), 1);
+        assert_eq!(stats.head_reversals, 2);
+        assert_eq!(stats.steps_per_state[&0], 4);
+        assert_eq!(stats.steps_per_state[&1], 4);
+        assert_eq!(observed.borrow().transition_hits, stats.transition_hits);
+
+        // Undoing steps keeps the statistics, so replayed steps are counted
+        // twice
+        let mut tm = TuringMachine::new(Box::new(Tape::tape(vec![Some(1); 20])));
+        tm.add_transition((0, Some(1)), (0, Some(1), Direction::Right));
+        tm.collect_stats(true);
+        tm.record_history(true);
+        tm.run(10, None);
+        assert!(tm.goto_step(5));
+        assert_eq!(tm.stats().map(|s| s.steps), Some(10));
+        assert!(tm.goto_step(10));
+        assert_eq!(tm.stats().map(|s| s.steps), Some(15));
+    }
+}
@@ -1,15 +1,114 @@
+use std::collections::HashSet;
 use std::fmt::{self, Debug, Display};
 use std::hash::Hash;
+use std::ops::Range;
+use crate::render::Renderer;
 
-pub trait Tapeable = Copy + Debug + Display + Eq + Hash;
+pub trait Tapeable = Copy + Debug + Display + Eq + Hash + 'static;
 
+#[derive(Clone)]
 pub struct Tape<V: Tapeable> {
     /// Tape positions from 0 to infinity
     positive_tape: Vec<Option<V>>,
     /// Tape positions from -1 to -infinity
     negative_tape: Vec<Option<V>>,
     /// The current head position
-    head_position: isize
+    head_position: isize,
+    /// Leftmost position the head may be on, if the tape is bounded on the left
+    left_end: Option<isize>,
+    /// Rightmost position the head may be on, if the tape is bounded on the
+    /// right
+    right_end: Option<isize>,
+    /// What happens, when the head tries to leave the bounds
+    boundary: Boundary,
+    /// The symbols enclosing the input of a linear bounded tape
+    end_markers: Option<(V, V)>,
+    /// The symbols that may be written on and loaded onto the tape. Without
+    /// one, everything is allowed and None is the blank.
+    alphabet: Option<Alphabet<V>>
+}
+
+/// The symbols a tape is allowed to contain.
+#[derive(Clone, Debug)]
+pub struct Alphabet<V> {
+    /// Symbols that may be written by the machine
+    tape: HashSet<V>,
+    /// Symbols the input may consist of
+    input: HashSet<V>,
+    /// The symbol every cell contains, before something has been written to it
+    blank: Option<V>
+}
+
+impl<V: Tapeable> Alphabet<V> {
+    /// Create an alphabet. The input symbols are always tape symbols as well,
+    /// so the tape symbols only need to contain the additional ones. The blank
+    /// is None normally, but any other symbol can be declared instead. It can
+    /// be written anywhere, but is never part of the input.
+    pub fn new<I, T>(input: I, tape: T, blank: Option<V>) -> Alphabet<V>
+        where I: IntoIterator<Item = V>, T: IntoIterator<Item = V>
+    {
+        let mut input: HashSet<V> = input.into_iter().collect();
+        if let Some(blank) = blank {
+            input.remove(&blank);
+        }
+        let tape = tape.into_iter().chain(input.iter().copied()).chain(blank).collect();
+
+        Alphabet { tape, input, blank }
+    }
+
+    pub fn blank(&self) -> Option<V> {
+        self.blank
+    }
+
+    /// Check if the symbol may be written on the tape.
+    pub fn is_tape_symbol(&self, symbol: Option<V>) -> bool {
+        symbol == self.blank || symbol.is_some_and(|s| self.tape.contains(&s))
+    }
+
+    /// Check if the symbol may be part of the input.
+    pub fn is_input_symbol(&self, symbol: Option<V>) -> bool {
+        symbol.is_some_and(|s| self.input.contains(&s))
+    }
+}
+
+/// Decides what happens, when the head of a bounded tape tries to move past
+/// the end of the tape.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Boundary {
+    /// The head stays where it is and the move is reported as an error.
+    Error,
+    /// The head stays where it is, as if it had bounced back.
+    Bounce
+}
+
+/// Errors that can occur, when working with a tape.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TapeError<V> {
+    /// The head has tried to move to the position, which is outside of the
+    /// bounds of the tape.
+    OutOfBounds {
+        position: isize
+    },
+    /// The symbol is not part of the alphabet of the tape, but has been
+    /// written to or loaded onto the position.
+    SymbolOutsideAlphabet {
+        symbol: Option<V>,
+        position: isize
+    }
+}
+
+impl<V: Display> Display for TapeError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            TapeError::OutOfBounds { position } => write!(f, "head moved out of the tape to position {}", position),
+            TapeError::SymbolOutsideAlphabet { symbol: Some(symbol), position } => {
+                write!(f, "symbol {} at position {} is not part of the alphabet", symbol, position)
+            },
+            TapeError::SymbolOutsideAlphabet { symbol: None, position } => {
+                write!(f, "symbol None at position {} is not part of the alphabet", position)
+            }
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -22,12 +121,13 @@ pub enum Direction {
 impl Direction {
     /// Parse a direction from a string. Returns None, if it is not a known
     /// direction.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str<S: AsRef<str>>(s: S) -> Option<Direction> {
         match s.as_ref().to_lowercase().as_ref() {
             "left" => Some(Direction::Left),
             "hold" => Some(Direction::Hold),
             "right" => Some(Direction::Right),
-            other => None
+            _ => None
         }
     }
 }
@@ -37,6 +137,13 @@ pub trait SimpleTape<V>: Display {
     /// Tape, which is at the new position.
     fn mv(&mut self, direction: Direction) -> Option<V>;
 
+    /// Like mv, but report an error if the Head cannot be moved, because it
+    /// would leave the bounds of the tape. The Head stays where it is then.
+    /// Tapes without bounds never fail.
+    fn try_mv(&mut self, direction: Direction) -> Result<Option<V>, TapeError<V>> {
+        Ok(self.mv(direction))
+    }
+
     /// Move the Head left and return the Value that is found there.
     fn mv_left(&mut self) -> Option<V>;
 
@@ -48,6 +155,65 @@ pub trait SimpleTape<V>: Display {
 
     fn write(&mut self, val: Option<V>);
 
+    /// Like write, but report an error and leave the tape unchanged if the
+    /// value is not part of the alphabet of the tape.
+    fn try_write(&mut self, val: Option<V>) -> Result<(), TapeError<V>> where V: Tapeable {
+        if self.alphabet().is_some_and(|a| !a.is_tape_symbol(val)) {
+            return Err(TapeError::SymbolOutsideAlphabet { symbol: val, position: self.head_position() });
+        }
+
+        self.write(val);
+        Ok(())
+    }
+
+    /// Read the Value of the cell at the position, regardless of where the
+    /// Head is.
+    fn cell(&self, position: isize) -> Option<V>;
+
+    /// The cells from radius positions left of the Head to radius positions
+    /// right of it, so the Head is always on the middle one.
+    fn window(&self, radius: usize) -> Vec<Option<V>> {
+        let radius = radius as isize;
+        let head = self.head_position();
+        (head - radius..=head + radius).map(|i| self.cell(i)).collect()
+    }
+
+    /// The current position of the Head. Position 0 is the first cell of the
+    /// input, everything to the left of it is negative.
+    fn head_position(&self) -> isize;
+
+    /// The range of positions that are stored on the tape. All cells outside of
+    /// it are blank, but it may also contain blanks itself.
+    fn extent(&self) -> Range<isize>;
+
+    /// Replace the contents of the tape with the input, starting at position
+    /// 0, and move the Head back to position 0.
+    fn load(&mut self, input: &[Option<V>]);
+
+    /// Like load, but report an error and leave the tape unchanged if the input
+    /// contains symbols, which are not part of the input alphabet.
+    fn try_load(&mut self, input: &[Option<V>]) -> Result<(), TapeError<V>> where V: Tapeable {
+        if let Some(alphabet) = self.alphabet() {
+            if let Some(position) = input.iter().position(|&v| !alphabet.is_input_symbol(v)) {
+                return Err(TapeError::SymbolOutsideAlphabet { symbol: input[position], position: position as isize });
+            }
+        }
+
+        self.load(input);
+        Ok(())
+    }
+
+    /// The alphabet the tape has been restricted to, if any.
+    fn alphabet(&self) -> Option<&Alphabet<V>>;
+
+    /// Restrict the symbols on the tape to the alphabet, or lift the
+    /// restriction with None. Cells that have not been written read as the
+    /// blank of the alphabet from now on.
+    fn set_alphabet(&mut self, alphabet: Option<Alphabet<V>>);
+
+    /// Create a copy of the tape, including its Head position.
+    fn boxed_clone(&self) -> Box<dyn SimpleTape<V>>;
+
     /// Get the tape contents as a slice. May contain leading or trailing blanks
     fn contents(&self) -> Vec<Option<V>>;
 
@@ -59,44 +225,114 @@ pub trait SimpleTape<V>: Display {
 impl<V: Tapeable> Tape<V> {
     /// Create a new, empty tape
     pub fn new() -> Tape<V> {
-        Tape {
-            positive_tape: Vec::new(),
-            negative_tape: Vec::new(),
-            head_position: 0
-        }
+        Tape::tape(Vec::new())
     }
 
     /// Create a Tape from the values in the slice
+    #[allow(clippy::self_named_constructors)]
     pub fn tape(tape: Vec<Option<V>>) -> Tape<V> {
         Tape {
             positive_tape: tape,
             negative_tape: Vec::new(),
-            head_position: 0
+            head_position: 0,
+            left_end: None,
+            right_end: None,
+            boundary: Boundary::Bounce,
+            end_markers: None,
+            alphabet: None
         }
     }
 
-    // Fill with None until the current head position, so that the infinite tape
-    // rule will not be broken.
-    fn fill_with_nones(&mut self) {
-        if self.head_position >= 0 {
-            let adj_hp = self.head_position as usize;
+    /// Create a tape for a linear bounded automaton. The input is enclosed by
+    /// the left marker at position -1 and the right marker directly behind it,
+    /// and the head can never leave the region between and including them.
+    /// The head starts on the first symbol of the input.
+    pub fn linear_bounded(input: Vec<Option<V>>, left_marker: V, right_marker: V, boundary: Boundary) -> Tape<V> {
+        let mut tape = Tape::new();
+        tape.boundary = boundary;
+        tape.end_markers = Some((left_marker, right_marker));
+        tape.load(&input);
+        tape
+    }
+
+    /// Create a tape that ends on the left of position 0 and is infinite to the
+    /// right. A move to the left on the first cell leaves the head where it
+    /// is, like on the tapes used in most textbooks.
+    pub fn one_way(tape: Vec<Option<V>>) -> Tape<V> {
+        let mut tape = Tape::tape(tape);
+        tape.left_end = Some(0);
+        tape
+    }
+
+    /// Check if the head is allowed to be on the position.
+    fn in_bounds(&self, position: isize) -> bool {
+        self.left_end.is_none_or(|end| position >= end) && self.right_end.is_none_or(|end| position <= end)
+    }
+
+    // Fill with None until the position, so that the infinite tape rule will
+    // not be broken.
+    fn fill_with_nones(&mut self, position: isize) {
+        if position >= 0 {
+            let adj_hp = position as usize;
             if adj_hp  >= self.positive_tape.len() {
                 self.positive_tape.resize_with(adj_hp + 1, || { None });
             }
         }
         else {
-            let adj_hp = self.head_position.abs() as usize - 1;
+            let adj_hp = position.unsigned_abs() - 1;
             if adj_hp >= self.negative_tape.len() {
                 self.negative_tape.resize_with(adj_hp + 1, || { None });
             }
         }
     }
+
+    /// Read the value at any position, regardless of where the Head is.
+    pub(crate) fn read_at(&self, position: isize) -> Option<V> {
+        let v = if position >= 0 {
+            self.positive_tape.get(position as usize).copied().flatten()
+        }
+        else {
+            self.negative_tape.get(position.unsigned_abs() - 1).copied().flatten()
+        };
+
+        v.or(self.blank())
+    }
+
+    /// The symbol that empty cells are read as.
+    fn blank(&self) -> Option<V> {
+        self.alphabet.as_ref().and_then(|a| a.blank())
+    }
+
+    /// Write the value at any position, regardless of where the Head is.
+    pub(crate) fn write_at(&mut self, position: isize, val: Option<V>) {
+        self.fill_with_nones(position);
+
+        if position >= 0 { self.positive_tape[position as usize] = val; }
+        else { self.negative_tape[position.unsigned_abs() - 1] = val; }
+    }
+}
+
+impl<V: Tapeable> Default for Tape<V> {
+    fn default() -> Tape<V> {
+        Tape::new()
+    }
 }
 
 impl<V: Tapeable> SimpleTape<V> for Tape<V> {
     fn mv(&mut self, direction: Direction) -> Option<V> {
-        self.head_position += direction as isize;
-        self.read()
+        self.try_mv(direction).unwrap_or_else(|_| self.read())
+    }
+
+    fn try_mv(&mut self, direction: Direction) -> Result<Option<V>, TapeError<V>> {
+        let position = self.head_position + direction as isize;
+        if self.in_bounds(position) {
+            self.head_position = position;
+        }
+        else if self.boundary == Boundary::Error {
+            return Err(TapeError::OutOfBounds { position });
+        }
+
+        Ok(self.read())
     }
 
     fn mv_left(&mut self) -> Option<V> {
@@ -108,68 +344,78 @@ impl<V: Tapeable> SimpleTape<V> for Tape<V> {
     }
 
     fn read(&self) -> Option<V> {
-        if self.head_position >= 0 {
-            if let Some(v) = self.positive_tape.get(self.head_position as usize) { *v }
-            else { None }
-        }
-        else {
-            if let Some(v) = self.negative_tape.get(self.head_position.abs() as usize - 1) { *v }
-            else { None }
-        }
+        self.read_at(self.head_position)
     }
 
     fn write(&mut self, val: Option<V>) {
-        self.fill_with_nones();
+        self.write_at(self.head_position, val);
+    }
+
+    fn cell(&self, position: isize) -> Option<V> {
+        self.read_at(position)
+    }
+
+    fn head_position(&self) -> isize {
+        self.head_position
+    }
+
+    fn extent(&self) -> Range<isize> {
+        -(self.negative_tape.len() as isize)..self.positive_tape.len() as isize
+    }
+
+    fn load(&mut self, input: &[Option<V>]) {
+        self.positive_tape = input.to_vec();
+        self.negative_tape.clear();
+        self.head_position = 0;
+
+        // Linear bounded tapes are fitted around the new input
+        if let Some((left_marker, right_marker)) = self.end_markers {
+            self.negative_tape.push(Some(left_marker));
+            self.positive_tape.push(Some(right_marker));
+            self.left_end = Some(-1);
+            self.right_end = Some(input.len() as isize);
+        }
+    }
+
+    fn alphabet(&self) -> Option<&Alphabet<V>> {
+        self.alphabet.as_ref()
+    }
+
+    fn set_alphabet(&mut self, alphabet: Option<Alphabet<V>>) {
+        self.alphabet = alphabet;
+    }
 
-        if self.head_position >= 0 { self.positive_tape[self.head_position as usize] = val; }
-        else { self.negative_tape[self.head_position.abs() as usize - 1] = val; }
+    fn boxed_clone(&self) -> Box<dyn SimpleTape<V>> {
+        Box::new(self.clone())
     }
 
     /// Get the tape contents as a slice. May contain leading or trailing blanks
     fn contents(&self) -> Vec<Option<V>> {
-        self.negative_tape.clone().into_iter().rev().chain(self.positive_tape.clone().into_iter()).collect()
+        self.extent().map(|i| self.read_at(i)).collect()
     }
 
     /// Like contents, but removes leading and trailing blanks. Blanks in the
     /// middle are accepted
     fn contents_trim_blanks(&self) -> Vec<Option<V>> {
-        let neg = self.negative_tape.clone();
-        let pos = self.positive_tape.clone();
+        let blank = self.blank();
 
         // Remove all Blanks from the start of the tape, but leave everything that may be trailing.
         let mut started = false;
-        let mut res: Vec<Option<V>> = neg.into_iter().rev().chain(pos.into_iter())
-            .filter(|x| {
-                started |= x.is_some();
-                x.is_some() || started
+        let mut res: Vec<Option<V>> = self.contents().into_iter()
+            .filter(|&x| {
+                started |= x != blank;
+                started
         }).collect();
 
         // Remove all blanks from the end of the tape, front has been dealt with already.
-        while let Some(None) = res.last() { res.pop(); }
+        while res.last().is_some_and(|&x| x == blank) { res.pop(); }
         res
     }
 }
 
 impl<V: Tapeable> Display for Tape<V> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "[ ")?;
-
-        // Print the negative side (reversed, since the highest index contains
-        // the leftmost value)
-        for v in self.negative_tape.iter().rev() {
-            if let Some(v) = v { write!(f, "{} ", v)?; }
-            else { write!(f, "NONE")?; }
-        }
-
-        // Print the right side in the normal order
-        for v in self.positive_tape.iter() {
-            if let Some(v) = v { write!(f, "{} ", v)?; }
-            else { write!(f, "NONE")?; }
-        }
-
-        write!(f, "]")?;
-
-        Ok(())
+        write!(f, "[ {} ]", Renderer::default().render_tape(self))
     }
 }
 
@@ -182,4 +428,65 @@ mod test {
         let tape = Tape::tape(vec![None, None, Some(true), None, None, Some(true), None, None]);
         assert_eq!(tape.contents_trim_blanks(), vec![Some(true), None, None, Some(true)]);
     }
+
+    #[test]
+    fn test_random_access() {
+        let mut tape = Tape::tape(vec![Some('a'), Some('b'), Some('c')]);
+        tape.mv_right();
+        assert_eq!(tape.cell(2), Some('c'));
+        assert_eq!(tape.cell(-5), None);
+        assert_eq!(tape.window(2), vec![None, Some('a'), Some('b'), Some('c'), None]);
+        assert_eq!(tape.window(0), vec![Some('b')]);
+    }
+
+    #[test]
+    fn test_linear_bounded() {
+        let mut tape = Tape::linear_bounded(vec![Some('a'), Some('b')], '<', '>', Boundary::Error);
+        assert_eq!(tape.contents(), vec![Some('<'), Some('a'), Some('b'), Some('>')]);
+
+        assert_eq!(tape.try_mv(Direction::Right), Ok(Some('b')));
+        assert_eq!(tape.try_mv(Direction::Right), Ok(Some('>')));
+        assert_eq!(tape.try_mv(Direction::Right), Err(TapeError::OutOfBounds { position: 3 }));
+        assert_eq!(tape.head_position(), 2);
+
+        let mut tape = Tape::linear_bounded(vec![Some('a')], '<', '>', Boundary::Bounce);
+        assert_eq!(tape.try_mv(Direction::Left), Ok(Some('<')));
+        assert_eq!(tape.try_mv(Direction::Left), Ok(Some('<')));
+        assert_eq!(tape.head_position(), -1);
+
+        // Loading another input moves the right end
+        tape.load(&[Some('a'), Some('a'), Some('a')]);
+        assert_eq!(tape.extent(), -1..4);
+    }
+
+    #[test]
+    fn test_one_way() {
+        let mut tape = Tape::one_way(vec![Some(1), Some(2)]);
+        assert_eq!(tape.mv_left(), Some(1));
+        assert_eq!(tape.try_mv(Direction::Left), Ok(Some(1)));
+        assert_eq!(tape.head_position(), 0);
+
+        // Unbounded to the right
+        tape.mv_right();
+        tape.mv_right();
+        tape.write(Some(3));
+        assert_eq!(tape.contents(), vec![Some(1), Some(2), Some(3)]);
+        tape.load(&[Some(4)]);
+        assert_eq!(tape.mv_left(), Some(4));
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let mut tape = Tape::new();
+        tape.set_alphabet(Some(Alphabet::new(['0', '1'], ['x'], Some('_'))));
+        assert_eq!(tape.try_load(&[Some('1'), Some('_')]), Err(TapeError::SymbolOutsideAlphabet { symbol: Some('_'), position: 1 }));
+        assert_eq!(tape.try_load(&[Some('1'), Some('0')]), Ok(()));
+
+        assert_eq!(tape.mv_left(), Some('_'));
+        assert_eq!(tape.try_write(Some('x')), Ok(()));
+        assert_eq!(tape.try_write(None), Err(TapeError::SymbolOutsideAlphabet { symbol: None, position: -1 }));
+        tape.mv(Direction::Right);
+        assert_eq!(tape.try_write(Some('_')), Ok(()));
+        assert_eq!(tape.contents_trim_blanks(), vec![Some('x'), Some('_'), Some('0')]);
+    }
 }
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use crate::tape::{Direction, Tapeable};
+use crate::turing_machine::{Stateable, TransitionTable};
+
+/// A tape cell that is split into N tracks, each holding its own symbol. A
+/// tape of Tracks can be used with every machine, a cell on which all tracks
+/// are blank is the blank None.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tracks<V, const N: usize>(pub [Option<V>; N]);
+
+impl<V: Copy, const N: usize> Tracks<V, N> {
+    /// Get the symbol on the track with the index.
+    pub fn get(&self, track: usize) -> Option<V> {
+        self.0[track]
+    }
+
+    /// Turn the tracks into a tape cell, which is blank if all tracks are.
+    fn into_cell(self) -> Option<Tracks<V, N>> {
+        if self.0.iter().all(|v| v.is_none()) { None } else { Some(self) }
+    }
+}
+
+impl<V: Display, const N: usize> Display for Tracks<V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "(")?;
+        for (i, v) in self.0.iter().enumerate() {
+            if i > 0 { write!(f, ", ")?; }
+            if let Some(v) = v { write!(f, "{}", v)?; }
+            else { write!(f, "None")?; }
+        }
+        write!(f, ")")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackError {
+    /// There is no track with this name.
+    UnknownTrack(String)
+}
+
+/// Transition on a multi-track tape that only looks at some of the tracks and
+/// only writes some of them.
+#[derive(Debug, Clone)]
+struct TrackRule<V, S, const N: usize> {
+    state: S,
+    /// What to match per track, None meaning anything
+    read: [Option<Option<V>>; N],
+    next_state: S,
+    /// What to write per track, None meaning leave it as it is
+    write: [Option<Option<V>>; N],
+    direction: Direction
+}
+
+/// Builder for the transitions of a machine working on a tape with N named
+/// tracks. Transitions are given as patterns on some of the tracks and are
+/// compiled into a normal transition table on Tracks, by expanding the
+/// patterns over the alphabet of every track.
+pub struct TrackTransitions<V: Tapeable, S: Stateable, const N: usize> {
+    names: [String; N],
+    /// The symbols that may appear on each track, apart from the blank
+    alphabets: [Vec<V>; N],
+    rules: Vec<TrackRule<V, S, N>>
+}
+
+impl<V: Tapeable, S: Stateable, const N: usize> TrackTransitions<V, S, N> {
+    /// Create the builder from the name and the alphabet of every track.
+    pub fn new(tracks: [(&str, Vec<V>); N]) -> TrackTransitions<V, S, N> {
+        TrackTransitions {
+            names: tracks.each_ref().map(|(name, _)| name.to_string()),
+            alphabets: tracks.map(|(_, alphabet)| alphabet),
+            rules: Vec::new()
+        }
+    }
+
+    /// Get the index of the track with the name.
+    pub fn track(&self, name: &str) -> Result<usize, TrackError> {
+        self.names.iter().position(|n| n == name).ok_or_else(|| TrackError::UnknownTrack(name.to_string()))
+    }
+
+    /// Build a tape cell with the given symbols on the named tracks and blanks
+    /// everywhere else.
+    pub fn cell(&self, symbols: &[(&str, Option<V>)]) -> Result<Option<Tracks<V, N>>, TrackError> {
+        Ok(Tracks(self.by_track(symbols)?.map(|v| v.flatten())).into_cell())
+    }
+
+    /// Add a transition, that applies when the machine is in the state and the
+    /// named tracks contain the given symbols, no matter what is on the other
+    /// tracks. Only the tracks named in write are overwritten. If multiple
+    /// transitions match the same cell, the one added first is used.
+    pub fn add(&mut self, state: S, read: &[(&str, Option<V>)], next_state: S, write: &[(&str, Option<V>)], direction: Direction) -> Result<(), TrackError> {
+        let rule = TrackRule {
+            state,
+            read: self.by_track(read)?,
+            next_state,
+            write: self.by_track(write)?,
+            direction
+        };
+        self.rules.push(rule);
+
+        Ok(())
+    }
+
+    /// Sort the symbols given by track name into an array by track index.
+    fn by_track(&self, symbols: &[(&str, Option<V>)]) -> Result<[Option<Option<V>>; N], TrackError> {
+        let mut res = [None; N];
+        for (name, symbol) in symbols {
+            res[self.track(name)?] = Some(*symbol);
+        }
+        Ok(res)
+    }
+
+    /// Expand the patterns into a transition table over all combinations of
+    /// track symbols that they match.
+    pub fn compile(&self) -> TransitionTable<Tracks<V, N>, S> {
+        let mut table = HashMap::new();
+
+        for rule in &self.rules {
+            // Every track can hold the blank or any symbol of its alphabet
+            let choices: Vec<Vec<Option<V>>> = (0..N).map(|i| match rule.read[i] {
+                Some(symbol) => vec![symbol],
+                None => std::iter::once(None).chain(self.alphabets[i].iter().copied().map(Some)).collect()
+            }).collect();
+
+            let mut indices = [0; N];
+            loop {
+                let read = Tracks(std::array::from_fn(|i| choices[i][indices[i]]));
+                let written = Tracks(std::array::from_fn(|i| rule.write[i].unwrap_or(read.0[i])));
+                table.entry((rule.state.clone(), read.into_cell()))
+                    .or_insert((rule.next_state.clone(), written.into_cell(), rule.direction));
+
+                // Advance to the next combination, like counting with a
+                // different base for every track
+                let mut i = 0;
+                while i < N {
+                    indices[i] += 1;
+                    if indices[i] < choices[i].len() { break; }
+                    indices[i] = 0;
+                    i += 1;
+                }
+                if i == N { break; }
+            }
+        }
+
+        table
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::Tape;
+    use crate::turing_machine::{Halting, RunOutcome, TuringMachine, Verdict};
+
+    #[test]
+    fn test_marking_track() {
+        let mut transitions = TrackTransitions::new([("input", vec!['a', 'b']), ("mark", vec!['x'])]);
+        // Mark every a, without touching the input track
+        transitions.add(0, &[("input", Some('a'))], 0, &[("mark", Some('x'))], Direction::Right).unwrap();
+        transitions.add(0, &[("input", Some('b'))], 0, &[], Direction::Right).unwrap();
+        transitions.add(0, &[("input", None)], 1, &[], Direction::Hold).unwrap();
+        assert_eq!(transitions.add(0, &[("output", None)], 1, &[], Direction::Hold), Err(TrackError::UnknownTrack("output".to_string())));
+
+        let input: Vec<_> = "aba".chars().map(|c| transitions.cell(&[("input", Some(c))]).unwrap()).collect();
+        let mut tm = TuringMachine::init_fully(Box::new(Tape::tape(input)), transitions.compile(), 0);
+        tm.add_halting_state(1);
+
+        assert_eq!(tm.run(100, None).outcome, RunOutcome::Halted(Verdict::Halt));
+
+        let mark = transitions.track("mark").unwrap();
+        let marks: Vec<_> = tm.tape().contents_trim_blanks().iter().map(|c| c.and_then(|c| c.get(mark))).collect();
+        assert_eq!(marks, vec![Some('x'), None, Some('x')]);
+        assert_eq!(tm.tape().contents_trim_blanks()[0], transitions.cell(&[("input", Some('a')), ("mark", Some('x'))]).unwrap());
+        assert_eq!(Tracks([Some('a'), None]).to_string(), "(a, None)");
+    }
+}
@@ -1,119 +1,993 @@
-use std::collections::HashMap;
-use std::ops::Deref;
-use crate::tape::{Direction, Tapeable, SimpleTape};
-
-pub type State = usize;
-pub type TransitionTable<V> = HashMap<(State, Option<V>), (State, Option<V>, Direction)>;
-
-pub struct TuringMachine<V: Tapeable> {
-    starting_state: State,
-    current_state: State,
-    tape: Box<SimpleTape<V>>,
-    transitions: TransitionTable<V>,
-}
-
-pub trait Transitionable<V> {
-    /// Reset the turing machine, so that it will start a calculation from the
-    /// start, as if it was newly initialised again. Beware that the last state
-    /// it was left in beforehand is not saved, so make sure to remember it
-    /// somewhere else if it still matters.
-    fn reset(&mut self);
-
-    /// Check what the next transition will be, without actually performing it
-    fn peek_transition(&self) -> (State, Option<V>, Direction);
-
-    /// Perform the next transition. Return true, if the turing machine wants
-    /// to continue running, false if it has stopped.
-    fn step(&mut self) -> bool;
-}
-
-impl<V: Tapeable> TuringMachine<V> {
-    /// Create a new turing machine with a tape inserted and empty transition function
-    pub fn new(tape: Box<SimpleTape<V>>) -> TuringMachine<V> {
-        TuringMachine {
-            starting_state: 0,
-            current_state: 0,
-            tape,
-            transitions: HashMap::new()
-        }
-    }
-
-    /// Create a new turing machine with a tape, empty transition table and a
-    /// starting state that may differ from the default, which is 0
-    pub fn with_starting_state(tape: Box<SimpleTape<V>>, starting_state: State) -> TuringMachine<V> {
-        TuringMachine {
-            starting_state,
-            current_state: starting_state,
-            tape,
-            transitions: HashMap::new()
-        }
-    }
-
-    /// Initialise the turing machine fully. Needs the tape containing the input,
-    /// the full transition table and the state the machine will start from.
-    pub fn init_fully(tape: Box<SimpleTape<V>>, transitions: TransitionTable<V>, starting_state: State) -> TuringMachine<V> {
-        TuringMachine {
-            starting_state,
-            current_state: starting_state,
-            tape,
-            transitions
-        }
-    }
-
-    /// Change the tape to be the one given as the argument. Keep in mind that
-    /// the head position can change, since it is not bound to the turing
-    /// machine, but to the tape itself.
-    pub fn insert_tape(&mut self, tape: Box<SimpleTape<V>>) {
-        self.tape = tape;
-    }
-
-    /// Add a transition to the transition table. If there was already a transition
-    /// registered to the cause, it will be replaced and returned
-    pub fn add_transition(&mut self, cause: (State, Option<V>), effect: (State, Option<V>, Direction)) -> Option<(State, Option<V>, Direction)> {
-        self.transitions.insert(cause, effect)
-    }
-
-    /// Print the state of the turing machine.
-    pub fn print(&self) {
-        println!("Turing Machine is in state: {}", self.current_state);
-        println!("Transition Table:");
-        for (k, v) in &self.transitions {
-            println!("{:?} -> {:?}", k, v);
-        }
-        println!("Tape contents: {}", &self.tape);
-    }
-
-    pub fn tape(&self) -> &SimpleTape<V> {
-        self.tape.deref()
-    }
-}
-
-impl<V: Tapeable> Transitionable<V> for TuringMachine<V> {
-    fn reset(&mut self) {
-        self.current_state = self.starting_state;
-    }
-
-    fn peek_transition(&self) -> (State, Option<V>, Direction) {
-        *self.transitions.get(&(self.current_state, self.tape.read())).expect("Could not read from transition table")
-    }
-
-    /// Make the next step of the turing machine. Returns true, if it is still
-    /// running. Returns false, if a holding state has been reached or an error
-    /// has occured.
-    fn step(&mut self) -> bool {
-        let (new_state, value, dir) = self.peek_transition();
-
-        // Check if a holding state has been reached
-        if self.current_state == new_state && self.tape.read() == value {
-            println!("Reached holding state");
-            return false;
-        }
-
-        // Change state and replace the tapes contents with the correct value
-        self.current_state = new_state;
-        self.tape.write(value);
-        self.tape.mv(dir);
-
-        true
-    }
-}
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug, Display};
+use std::hash::Hash;
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+use crate::breakpoint::Breakpoint;
+use crate::observer::{Observer, TransitionEvent};
+use crate::stats::RunStats;
+use crate::tape::{Alphabet, Direction, Tapeable, SimpleTape, TapeError};
+
+pub trait Stateable = Clone + Debug + Display + Eq + Hash;
+
+/// The default state type, for machines with numbered states.
+pub type State = usize;
+/// Maps the state and the symbol read to the next state, the symbol to write
+/// and the direction to move in. Machines on other kinds of tapes can use
+/// their own direction type.
+pub type TransitionTable<V, S = State, D = Direction> = HashMap<(S, Option<V>), (S, Option<V>, D)>;
+
+/// The way a turing machine has come to a halt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Verdict {
+    /// An accepting state has been reached, the input is part of the language.
+    Accept,
+    /// A rejecting state has been reached, the input is not part of the language.
+    Reject,
+    /// The machine has stopped without deciding anything, for instance because
+    /// it only computes a function.
+    Halt
+}
+
+/// Decides when a turing machine is considered to have halted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HaltingPolicy {
+    /// Only halt, when one of the declared accepting, rejecting or halting
+    /// states has been reached.
+    DeclaredStates,
+    /// Like DeclaredStates, but additionally halt when a transition would
+    /// neither change the state nor the symbol under the head. This does not
+    /// recognise loops that move the head, like (q, None) -> (q, None, Right).
+    SelfLoop
+}
+
+/// The states in which a turing machine stops running.
+#[derive(Debug, Clone)]
+pub struct FinalStates<S = State> {
+    pub accepting: HashSet<S>,
+    pub rejecting: HashSet<S>,
+    pub halting: HashSet<S>
+}
+
+impl<S: Stateable> Default for FinalStates<S> {
+    fn default() -> FinalStates<S> {
+        FinalStates {
+            accepting: HashSet::new(),
+            rejecting: HashSet::new(),
+            halting: HashSet::new()
+        }
+    }
+}
+
+impl<S: Stateable> FinalStates<S> {
+    /// Get the verdict the machine arrives at, when it is in the given state.
+    /// Returns None, if the state is not a final state. If a state has been
+    /// declared multiple times, accepting wins over rejecting, which wins over
+    /// halting.
+    pub fn verdict(&self, state: &S) -> Option<Verdict> {
+        if self.accepting.contains(state) { Some(Verdict::Accept) }
+        else if self.rejecting.contains(state) { Some(Verdict::Reject) }
+        else if self.halting.contains(state) { Some(Verdict::Halt) }
+        else { None }
+    }
+}
+
+/// Machines that stop, once they have reached one of their final states.
+pub trait Halting<S: Stateable = State> {
+    fn final_states(&self) -> &FinalStates<S>;
+
+    fn final_states_mut(&mut self) -> &mut FinalStates<S>;
+
+    /// Declare a state in which the machine accepts its input and stops.
+    fn add_accepting_state(&mut self, state: S) {
+        self.final_states_mut().accepting.insert(state);
+    }
+
+    /// Declare a state in which the machine rejects its input and stops.
+    fn add_rejecting_state(&mut self, state: S) {
+        self.final_states_mut().rejecting.insert(state);
+    }
+
+    /// Declare a state in which the machine stops without accepting or
+    /// rejecting.
+    fn add_halting_state(&mut self, state: S) {
+        self.final_states_mut().halting.insert(state);
+    }
+}
+
+/// The result of performing a single step of a turing machine. Machines on
+/// tapes with more than one dimension report the head position with their own
+/// position type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome<V, S = State, P = isize> {
+    /// A transition has been performed and the machine wants to continue.
+    Continued,
+    /// The machine is in a final state, or its halting policy decided that it
+    /// should stop. It will not do anything anymore.
+    Halted(Verdict),
+    /// There is no transition for the state the machine is in and the symbol
+    /// under the head. The machine stays exactly as it was before the step.
+    NoTransition {
+        state: S,
+        symbol: Option<V>,
+        head_position: P
+    },
+    /// The transition could not be performed on the tape, for example because
+    /// the head would have left a bounded tape. The machine stays exactly as
+    /// it was before the step.
+    TapeError(TapeError<V>)
+}
+
+/// The reason a bounded run of a turing machine has ended. Machines on other
+/// kinds of tapes can use their own type of head position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome<V, S = State, P = isize> {
+    /// The machine has halted with the given verdict.
+    Halted(Verdict),
+    /// The machine got stuck, because there was no transition defined for the
+    /// state and the symbol under the head.
+    NoTransition {
+        state: S,
+        symbol: Option<V>,
+        head_position: P
+    },
+    /// The machine got stuck, because a transition could not be performed on
+    /// the tape.
+    TapeError(TapeError<V>),
+    /// The step budget has been used up before the machine halted.
+    StepLimitReached,
+    /// The time limit has run out before the machine halted.
+    TimeLimitReached,
+    /// The breakpoint with the given id has fired.
+    Breakpoint(usize),
+    /// The configuration of the machine after step start + length is the same
+    /// as the one after step start, so it will never halt.
+    Cycle {
+        start: usize,
+        length: usize
+    }
+}
+
+/// Summary of a bounded run of a turing machine.
+pub struct RunReport<V, S = State> {
+    pub outcome: RunOutcome<V, S>,
+    /// The number of transitions performed during this run.
+    pub steps: usize,
+    pub final_state: S,
+    /// A copy of the tape after the run. It is of the same kind as the tape of
+    /// the machine, so a compact tape is not expanded into single cells
+    /// unless its contents are asked for.
+    pub tape: Box<dyn SimpleTape<V>>
+}
+
+/// Everything needed to undo a single transition of a turing machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepRecord<V, S = State> {
+    /// The state the machine was in before the transition
+    pub previous_state: S,
+    /// The symbol that has been overwritten by the transition
+    pub previous_symbol: Option<V>,
+    /// How far the head has actually moved, negative values being to the left
+    pub head_move: isize
+}
+
+/// Everything that determines how a machine continues, with the tape contents
+/// stripped of leading and trailing blanks, so that configurations only
+/// differing in the allocated blanks compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Configuration<V, S> {
+    state: S,
+    head_position: isize,
+    /// Position of the first cell in cells
+    offset: isize,
+    cells: Vec<Option<V>>
+}
+
+impl<V: Tapeable, S: Stateable> Configuration<V, S> {
+    fn of(state: &S, tape: &dyn SimpleTape<V>) -> Configuration<V, S> {
+        let blank = tape.alphabet().and_then(|a| a.blank());
+        let mut cells = tape.contents();
+        let leading_blanks = cells.iter().take_while(|&&c| c == blank).count();
+        cells.drain(..leading_blanks);
+        while cells.last() == Some(&blank) { cells.pop(); }
+
+        // A blank tape has no first cell, so the offset must not depend on
+        // how many blanks happen to be allocated
+        let offset = if cells.is_empty() { 0 } else { tape.extent().start + leading_blanks as isize };
+
+        Configuration {
+            state: state.clone(),
+            head_position: tape.head_position(),
+            offset,
+            cells
+        }
+    }
+}
+
+/// Check that every symbol on the tape is part of the alphabet. Cells that
+/// have never been written are blank under any alphabet.
+fn check_symbols<V: Tapeable>(tape: &dyn SimpleTape<V>, alphabet: &Alphabet<V>) -> Result<(), TapeError<V>> {
+    match tape.extent().find(|&i| tape.cell(i).is_some() && !alphabet.is_tape_symbol(tape.cell(i))) {
+        Some(position) => Err(TapeError::SymbolOutsideAlphabet { symbol: tape.cell(position), position }),
+        None => Ok(())
+    }
+}
+
+/// Name of a tape symbol as it is written in the parse format, where a blank
+/// is called None.
+fn symbol_name<V: Display>(symbol: &Option<V>) -> String {
+    match symbol {
+        Some(v) => v.to_string(),
+        None => "None".to_string()
+    }
+}
+
+impl<V, S, P> StepOutcome<V, S, P> {
+    /// The outcome of a run that ends with this step, or None if the machine
+    /// wants to continue.
+    pub(crate) fn into_run_outcome(self) -> Option<RunOutcome<V, S, P>> {
+        match self {
+            StepOutcome::Continued => None,
+            StepOutcome::Halted(verdict) => Some(RunOutcome::Halted(verdict)),
+            StepOutcome::NoTransition { state, symbol, head_position } => {
+                Some(RunOutcome::NoTransition { state, symbol, head_position })
+            },
+            StepOutcome::TapeError(err) => Some(RunOutcome::TapeError(err))
+        }
+    }
+}
+
+impl<V: Display, S: Display, P: Debug> Display for StepOutcome<V, S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            StepOutcome::Continued => write!(f, "continued"),
+            StepOutcome::Halted(verdict) => write!(f, "halted: {:?}", verdict),
+            StepOutcome::NoTransition { state, symbol, head_position } => {
+                write!(f, "no transition for state {} reading {} at position {:?}", state, symbol_name(symbol), head_position)
+            },
+            StepOutcome::TapeError(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl<V: Display, S: Display, P: Debug> Display for RunOutcome<V, S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            RunOutcome::Halted(verdict) => write!(f, "halted: {:?}", verdict),
+            RunOutcome::NoTransition { state, symbol, head_position } => {
+                write!(f, "no transition for state {} reading {} at position {:?}", state, symbol_name(symbol), head_position)
+            },
+            RunOutcome::TapeError(err) => write!(f, "{}", err),
+            RunOutcome::StepLimitReached => write!(f, "step limit reached"),
+            RunOutcome::TimeLimitReached => write!(f, "time limit reached"),
+            RunOutcome::Breakpoint(id) => write!(f, "breakpoint {} hit", id),
+            RunOutcome::Cycle { start, length } => {
+                write!(f, "provably non-halting: cycle of length {} starting at step {}", length, start)
+            }
+        }
+    }
+}
+
+impl<V: Clone, S: Clone> Clone for RunReport<V, S> {
+    fn clone(&self) -> Self {
+        RunReport {
+            outcome: self.outcome.clone(),
+            steps: self.steps,
+            final_state: self.final_state.clone(),
+            tape: self.tape.boxed_clone()
+        }
+    }
+}
+
+impl<V: Debug, S: Debug> Debug for RunReport<V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("RunReport")
+            .field("outcome", &self.outcome)
+            .field("steps", &self.steps)
+            .field("final_state", &self.final_state)
+            .field("tape", &self.tape.contents())
+            .finish()
+    }
+}
+
+/// Reports are equal, if the tapes have the same contents, no matter what kind
+/// of tape they are.
+impl<V: PartialEq, S: PartialEq> PartialEq for RunReport<V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.outcome == other.outcome && self.steps == other.steps && self.final_state == other.final_state
+            && self.tape.contents() == other.tape.contents()
+    }
+}
+
+impl<V: Eq, S: Eq> Eq for RunReport<V, S> {}
+
+pub struct TuringMachine<V: Tapeable, S: Stateable = State> {
+    starting_state: S,
+    current_state: S,
+    tape: Box<dyn SimpleTape<V>>,
+    /// Copy of the tape as it was inserted or loaded, used to restore it on
+    /// reset
+    initial_tape: Box<dyn SimpleTape<V>>,
+    transitions: TransitionTable<V, S>,
+    final_states: FinalStates<S>,
+    halting_policy: HaltingPolicy,
+    /// Number of transitions performed since the last reset
+    steps: usize,
+    /// Undo log of the transitions performed, if recording is enabled
+    history: Option<Vec<StepRecord<V, S>>>,
+    observers: Vec<Box<dyn Observer<V, S>>>,
+    breakpoints: Vec<Breakpoint<V, S>>,
+    /// The first breakpoint that fired during the last step, if any
+    triggered_breakpoint: Option<usize>,
+    /// The configurations seen during bounded runs and the step they have
+    /// been seen at, if cycle detection is enabled
+    seen_configurations: Option<HashMap<Configuration<V, S>, usize>>,
+    /// Statistics since the last reset, if they are being collected
+    stats: Option<RunStats<V, S>>
+}
+
+pub trait Transitionable<V, S = State> {
+    /// Reset the turing machine, so that it will start a calculation from the
+    /// start, as if it was newly initialised again. This restores the starting
+    /// state as well as the tape contents and head position the tape had, when
+    /// it was last inserted or loaded. Beware that the last configuration it
+    /// was left in beforehand is not saved, so make sure to remember it
+    /// somewhere else if it still matters.
+    fn reset(&mut self);
+
+    /// Check what the next transition will be, without actually performing it.
+    /// Returns None, if the transition function is not defined for the current
+    /// state and the symbol under the head.
+    fn peek_transition(&self) -> Option<(S, Option<V>, Direction)>;
+
+    /// Perform the next transition and report, if the turing machine wants to
+    /// continue running, has stopped or got stuck.
+    fn step(&mut self) -> StepOutcome<V, S>;
+}
+
+impl<V: Tapeable, S: Stateable + Default> TuringMachine<V, S> {
+    /// Create a new turing machine with a tape inserted and empty transition
+    /// function. It starts in the default state, which is 0 for numbered states.
+    pub fn new(tape: Box<dyn SimpleTape<V>>) -> TuringMachine<V, S> {
+        TuringMachine::init_fully(tape, HashMap::new(), S::default())
+    }
+}
+
+impl<V: Tapeable, S: Stateable> TuringMachine<V, S> {
+    /// Create a new turing machine with a tape, empty transition table and a
+    /// starting state that may differ from the default, which is 0
+    pub fn with_starting_state(tape: Box<dyn SimpleTape<V>>, starting_state: S) -> TuringMachine<V, S> {
+        TuringMachine::init_fully(tape, HashMap::new(), starting_state)
+    }
+
+    /// Initialise the turing machine fully. Needs the tape containing the input,
+    /// the full transition table and the state the machine will start from.
+    pub fn init_fully(tape: Box<dyn SimpleTape<V>>, transitions: TransitionTable<V, S>, starting_state: S) -> TuringMachine<V, S> {
+        TuringMachine {
+            current_state: starting_state.clone(),
+            starting_state,
+            initial_tape: tape.boxed_clone(),
+            tape,
+            transitions,
+            final_states: FinalStates::default(),
+            halting_policy: HaltingPolicy::DeclaredStates,
+            steps: 0,
+            history: None,
+            observers: Vec::new(),
+            breakpoints: Vec::new(),
+            triggered_breakpoint: None,
+            seen_configurations: None,
+            stats: None
+        }
+    }
+
+    /// Change the tape to be the one given as the argument. Keep in mind that
+    /// the head position can change, since it is not bound to the turing
+    /// machine, but to the tape itself. The same goes for the alphabet, so one
+    /// set with set_alphabet is replaced by the alphabet of the new tape. The
+    /// step count, history and statistics start over, the current state is
+    /// kept.
+    pub fn insert_tape(&mut self, tape: Box<dyn SimpleTape<V>>) {
+        self.initial_tape = tape.boxed_clone();
+        self.tape = tape;
+
+        // The steps taken so far were on another tape
+        self.steps = 0;
+        if let Some(history) = self.history.as_mut() {
+            history.clear();
+        }
+        if let Some(seen) = self.seen_configurations.as_mut() {
+            seen.clear();
+        }
+        if self.stats.is_some() {
+            self.stats = Some(RunStats::new());
+        }
+    }
+
+    /// Load the input onto the tape, starting at position 0 with the head on
+    /// the first symbol, and reset the machine. The input is remembered, so
+    /// that every following reset will start the machine on it again.
+    /// If the input contains symbols outside of the input alphabet of the tape,
+    /// an error is returned and the machine is left unchanged.
+    pub fn load_input(&mut self, input: &[Option<V>]) -> Result<(), TapeError<V>> {
+        self.initial_tape.try_load(input)?;
+        self.reset();
+        Ok(())
+    }
+
+    /// The alphabet of the tape the machine works on, if it has one.
+    pub fn alphabet(&self) -> Option<&Alphabet<V>> {
+        self.tape.alphabet()
+    }
+
+    /// Restrict the tape of the machine to the alphabet. Transitions writing
+    /// symbols outside of it will fail, and so will loading input that is not
+    /// made up of input symbols. If the tape already contains symbols outside
+    /// of the alphabet, an error is returned and nothing is changed.
+    pub fn set_alphabet(&mut self, alphabet: Alphabet<V>) -> Result<(), TapeError<V>> {
+        check_symbols(self.initial_tape.deref(), &alphabet)?;
+        check_symbols(self.tape.deref(), &alphabet)?;
+
+        self.initial_tape.set_alphabet(Some(alphabet.clone()));
+        self.tape.set_alphabet(Some(alphabet));
+        Ok(())
+    }
+
+    /// Add a transition to the transition table. If there was already a transition
+    /// registered to the cause, it will be replaced and returned
+    pub fn add_transition(&mut self, cause: (S, Option<V>), effect: (S, Option<V>, Direction)) -> Option<(S, Option<V>, Direction)> {
+        self.transitions.insert(cause, effect)
+    }
+
+    /// Change when the machine is considered to have halted. The default is to
+    /// only halt in the declared final states.
+    pub fn set_halting_policy(&mut self, policy: HaltingPolicy) {
+        self.halting_policy = policy;
+    }
+
+    /// Get the verdict of the machine, if it is currently in a final state.
+    pub fn verdict(&self) -> Option<Verdict> {
+        self.final_states.verdict(&self.current_state)
+    }
+
+    /// The state the machine is currently in.
+    pub fn current_state(&self) -> &S {
+        &self.current_state
+    }
+
+    /// The number of transitions that have been performed since the machine
+    /// has last been reset.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Register an observer, which will be notified of every transition, halt
+    /// and error of the machine from now on.
+    pub fn add_observer(&mut self, observer: Box<dyn Observer<V, S>>) {
+        self.observers.push(observer);
+    }
+
+    /// Add a breakpoint, which interrupts bounded runs when it fires. Returns
+    /// the id, which is reported by the run when this breakpoint has fired.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint<V, S>) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    /// Remove all breakpoints. Ids that have been handed out before are no
+    /// longer valid.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Enable or disable collecting execution statistics. Enabling starts with
+    /// empty statistics, which are also cleared on every reset. Undoing a step
+    /// does not roll them back, so steps that are undone and performed again
+    /// are counted twice.
+    pub fn collect_stats(&mut self, enabled: bool) {
+        self.stats = if enabled { Some(RunStats::new()) } else { None };
+    }
+
+    /// The statistics collected since the last reset, if enabled.
+    pub fn stats(&self) -> Option<&RunStats<V, S>> {
+        self.stats.as_ref()
+    }
+
+    /// Enable or disable detecting cycles during bounded runs. When enabled,
+    /// every configuration the machine is in during a run is remembered, and
+    /// the run stops as soon as one repeats, since the machine is then known
+    /// to never halt. Beware that this keeps a copy of the tape for every step,
+    /// expanded into single cells even for compact tapes like RleTape.
+    pub fn detect_cycles(&mut self, enabled: bool) {
+        self.seen_configurations = if enabled { Some(HashMap::new()) } else { None };
+    }
+
+    /// Enable or disable recording the undo log, which is needed to step
+    /// backwards. Enabling starts with an empty log, so steps taken before
+    /// cannot be undone. Disabling throws the log away.
+    pub fn record_history(&mut self, enabled: bool) {
+        self.history = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// The recorded undo log, oldest step first. None, if recording is disabled.
+    pub fn history(&self) -> Option<&[StepRecord<V, S>]> {
+        self.history.as_deref()
+    }
+
+    /// Undo the last transition, restoring the state, the overwritten symbol
+    /// and the head position. Returns false, if there is no recorded step left
+    /// to undo.
+    pub fn step_back(&mut self) -> bool {
+        let record = match self.history.as_mut().and_then(|h| h.pop()) {
+            Some(record) => record,
+            None => return false
+        };
+
+        let direction = if record.head_move < 0 { Direction::Right } else { Direction::Left };
+        for _ in 0..record.head_move.abs() {
+            self.tape.mv(direction);
+        }
+        self.tape.write(record.previous_symbol);
+        self.current_state = record.previous_state;
+        self.steps -= 1;
+
+        // Configurations seen after this step may never be reached again
+        let steps = self.steps;
+        if let Some(seen) = self.seen_configurations.as_mut() {
+            seen.retain(|_, &mut step| step < steps);
+        }
+
+        true
+    }
+
+    /// Move the machine to the configuration it had after n steps, stepping
+    /// backwards through the history or replaying transitions forwards, as
+    /// needed. Returns false, if step n cannot be reached, because it is not
+    /// recorded or the machine stops before it. The machine is left at the
+    /// closest step it could reach in that case.
+    pub fn goto_step(&mut self, n: usize) -> bool {
+        while self.steps > n {
+            if !self.step_back() {
+                return false;
+            }
+        }
+        while self.steps < n {
+            if self.step() != StepOutcome::Continued && self.steps < n {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Run the machine until it halts or gets stuck, but perform at most
+    /// max_steps transitions and stop when the time limit, if any, is exceeded,
+    /// a breakpoint fires or a cycle has been detected.
+    /// The machine is left in the configuration it was stopped in, so calling
+    /// run again continues where the last run ended.
+    pub fn run(&mut self, max_steps: usize, time_limit: Option<Duration>) -> RunReport<V, S> {
+        let started = Instant::now();
+        let first_step = self.steps;
+
+        let outcome = loop {
+            if let Some(verdict) = self.verdict() {
+                break RunOutcome::Halted(verdict);
+            }
+            if self.steps - first_step >= max_steps {
+                break RunOutcome::StepLimitReached;
+            }
+            if time_limit.is_some_and(|limit| started.elapsed() >= limit) {
+                break RunOutcome::TimeLimitReached;
+            }
+            if let Some(seen) = self.seen_configurations.as_mut() {
+                let configuration = Configuration::of(&self.current_state, self.tape.deref());
+                match seen.get(&configuration) {
+                    // The configuration may have been seen at this very step
+                    // already, when a previous run has stopped here
+                    Some(&start) if start < self.steps => {
+                        break RunOutcome::Cycle { start, length: self.steps - start };
+                    },
+                    _ => { seen.insert(configuration, self.steps); }
+                }
+            }
+
+            match self.step() {
+                StepOutcome::Continued => {
+                    if let Some(id) = self.triggered_breakpoint {
+                        break RunOutcome::Breakpoint(id);
+                    }
+                },
+                StepOutcome::Halted(verdict) => break RunOutcome::Halted(verdict),
+                StepOutcome::NoTransition { state, symbol, head_position } => {
+                    break RunOutcome::NoTransition { state, symbol, head_position };
+                },
+                StepOutcome::TapeError(err) => break RunOutcome::TapeError(err)
+            }
+        };
+
+        RunReport {
+            outcome,
+            steps: self.steps - first_step,
+            final_state: self.current_state.clone(),
+            tape: self.tape.boxed_clone()
+        }
+    }
+
+    /// Print the state of the turing machine.
+    pub fn print(&self) {
+        println!("Turing Machine is in state: {}", self.current_state);
+        println!("Transition Table:");
+        for ((q, a), (q_next, a_next, d)) in &self.transitions {
+            println!("({}, {}) -> ({}, {}, {:?})", q, symbol_name(a), q_next, symbol_name(a_next), d);
+        }
+        println!("Tape contents: {}", self.tape);
+    }
+
+    pub fn tape(&self) -> &dyn SimpleTape<V> {
+        self.tape.deref()
+    }
+}
+
+impl<V: Tapeable, S: Stateable> Transitionable<V, S> for TuringMachine<V, S> {
+    fn reset(&mut self) {
+        self.current_state = self.starting_state.clone();
+        self.tape = self.initial_tape.boxed_clone();
+        self.steps = 0;
+        if let Some(history) = self.history.as_mut() {
+            history.clear();
+        }
+        if let Some(seen) = self.seen_configurations.as_mut() {
+            seen.clear();
+        }
+        if self.stats.is_some() {
+            self.stats = Some(RunStats::new());
+        }
+    }
+
+    fn peek_transition(&self) -> Option<(S, Option<V>, Direction)> {
+        self.transitions.get(&(self.current_state.clone(), self.tape.read())).cloned()
+    }
+
+    /// Make the next step of the turing machine. Returns Continued, if it is
+    /// still running and Halted, if a final state has been reached, either
+    /// before or by this step. If the transition function is not defined for
+    /// the current configuration, nothing is changed and NoTransition is
+    /// returned.
+    fn step(&mut self) -> StepOutcome<V, S> {
+        if let Some(verdict) = self.verdict() {
+            return StepOutcome::Halted(verdict);
+        }
+
+        let (new_state, value, dir) = match self.peek_transition() {
+            Some(transition) => transition,
+            None => {
+                let outcome = StepOutcome::NoTransition {
+                    state: self.current_state.clone(),
+                    symbol: self.tape.read(),
+                    head_position: self.tape.head_position()
+                };
+                for observer in &mut self.observers {
+                    observer.on_error(&outcome);
+                }
+                return outcome;
+            }
+        };
+
+        // With the self loop heuristic, a transition that does not change
+        // anything is treated as a holding state
+        if self.halting_policy == HaltingPolicy::SelfLoop && self.current_state == new_state && self.tape.read() == value {
+            for observer in &mut self.observers {
+                observer.on_halt(Verdict::Halt, &self.current_state);
+            }
+            return StepOutcome::Halted(Verdict::Halt);
+        }
+
+        // Replace the tapes contents with the correct value and move. If the
+        // tape refuses either, the symbol is restored, so that the machine is
+        // left as it was
+        let previous_symbol = self.tape.read();
+        let previous_position = self.tape.head_position();
+        let performed = self.tape.try_write(value).and_then(|_| {
+            self.tape.try_mv(dir).inspect_err(|_| self.tape.write(previous_symbol))
+        });
+        if let Err(err) = performed {
+            let outcome = StepOutcome::TapeError(err);
+            for observer in &mut self.observers {
+                observer.on_error(&outcome);
+            }
+            return outcome;
+        }
+        let previous_state = std::mem::replace(&mut self.current_state, new_state);
+        self.steps += 1;
+
+        let event = TransitionEvent {
+            step: self.steps,
+            from_state: previous_state,
+            read: previous_symbol,
+            written: value,
+            direction: dir,
+            to_state: self.current_state.clone(),
+            head_position: previous_position,
+            new_head_position: self.tape.head_position()
+        };
+        for observer in &mut self.observers {
+            observer.on_transition(&event);
+        }
+        self.triggered_breakpoint = self.breakpoints.iter().position(|b| b.matches(&event));
+        if let Some(stats) = self.stats.as_mut() {
+            stats.record(&event);
+        }
+
+        if let Some(history) = self.history.as_mut() {
+            history.push(StepRecord {
+                previous_state: event.from_state,
+                previous_symbol: event.read,
+                head_move: event.new_head_position - event.head_position
+            });
+        }
+
+        match self.verdict() {
+            Some(verdict) => {
+                for observer in &mut self.observers {
+                    observer.on_halt(verdict, &self.current_state);
+                }
+                StepOutcome::Halted(verdict)
+            },
+            None => StepOutcome::Continued
+        }
+    }
+}
+
+impl<V: Tapeable, S: Stateable> Halting<S> for TuringMachine<V, S> {
+    fn final_states(&self) -> &FinalStates<S> {
+        &self.final_states
+    }
+
+    fn final_states_mut(&mut self) -> &mut FinalStates<S> {
+        &mut self.final_states
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tape::{Boundary, Tape};
+
+    #[test]
+    fn test_missing_transition() {
+        let tape = Tape::tape(vec![Some(true), Some(false)]);
+        let mut tm = TuringMachine::new(Box::new(tape));
+
+        // Only define what to do on true, so the machine gets stuck on the
+        // second cell.
+        tm.add_transition((0, Some(true)), (1, Some(true), Direction::Right));
+
+        assert_eq!(tm.step(), StepOutcome::Continued);
+        assert_eq!(tm.step(), StepOutcome::NoTransition { state: 1, symbol: Some(false), head_position: 1 });
+
+        // Being stuck must not change anything.
+        assert_eq!(tm.step(), StepOutcome::NoTransition { state: 1, symbol: Some(false), head_position: 1 });
+        assert_eq!(tm.tape().contents(), vec![Some(true), Some(false)]);
+    }
+
+    #[test]
+    fn test_final_states() {
+        // Accept words that only consist of true, reject as soon as false is read
+        let mut tm = TuringMachine::new(Box::new(Tape::tape(vec![Some(true), Some(true)])));
+        tm.add_transition((0, Some(true)), (0, Some(true), Direction::Right));
+        tm.add_transition((0, Some(false)), (2, Some(false), Direction::Hold));
+        tm.add_transition((0, None), (1, None, Direction::Hold));
+        tm.add_accepting_state(1);
+        tm.add_rejecting_state(2);
+
+        while tm.step() == StepOutcome::Continued {}
+        assert_eq!(tm.verdict(), Some(Verdict::Accept));
+        assert_eq!(tm.step(), StepOutcome::Halted(Verdict::Accept));
+
+        assert_eq!(tm.load_input(&[Some(true), Some(false)]), Ok(()));
+        while tm.step() == StepOutcome::Continued {}
+        assert_eq!(tm.verdict(), Some(Verdict::Reject));
+    }
+
+    #[test]
+    fn test_self_loop_policy() {
+        let mut tm = TuringMachine::new(Box::new(Tape::tape(vec![Some(true)])));
+        tm.add_transition((0, Some(true)), (0, Some(true), Direction::Hold));
+
+        // Without the heuristic, the machine just keeps going
+        assert_eq!(tm.step(), StepOutcome::Continued);
+        assert_eq!(tm.step(), StepOutcome::Continued);
+
+        tm.set_halting_policy(HaltingPolicy::SelfLoop);
+        assert_eq!(tm.step(), StepOutcome::Halted(Verdict::Halt));
+    }
+
+    #[test]
+    fn test_bounded_run() {
+        // Walks to the right forever
+        let mut tm = TuringMachine::new(Box::new(Tape::<bool>::new()));
+        tm.add_transition((0, None), (0, None, Direction::Right));
+
+        let report = tm.run(100, None);
+        assert_eq!(report.outcome, RunOutcome::StepLimitReached);
+        assert_eq!(report.steps, 100);
+        assert_eq!(report.final_state, 0);
+
+        let report = tm.run(100, Some(Duration::from_secs(0)));
+        assert_eq!(report.outcome, RunOutcome::TimeLimitReached);
+        assert_eq!(report.steps, 0);
+        assert_eq!(tm.steps(), 100);
+    }
+
+    #[test]
+    fn test_reset_restores_input() {
+        // Inverts the input and halts on the first blank
+        let mut tm = TuringMachine::new(Box::new(Tape::new()));
+        tm.add_transition((0, Some(true)), (0, Some(false), Direction::Right));
+        tm.add_transition((0, Some(false)), (0, Some(true), Direction::Right));
+        tm.add_transition((0, None), (1, None, Direction::Hold));
+        tm.add_halting_state(1);
+
+        assert_eq!(tm.load_input(&[Some(true), Some(true), Some(false)]), Ok(()));
+        tm.run(100, None);
+        assert_eq!(tm.tape().contents_trim_blanks(), vec![Some(false), Some(false), Some(true)]);
+        assert_eq!(tm.tape().head_position(), 3);
+
+        tm.reset();
+        assert_eq!(tm.tape().contents_trim_blanks(), vec![Some(true), Some(true), Some(false)]);
+        assert_eq!(tm.tape().head_position(), 0);
+        assert_eq!(tm.steps(), 0);
+
+        // The same machine can be run on another input without rebuilding it
+        assert_eq!(tm.load_input(&[Some(false)]), Ok(()));
+        let report = tm.run(100, None);
+        assert_eq!(report.outcome, RunOutcome::Halted(Verdict::Halt));
+        assert_eq!(tm.tape().contents_trim_blanks(), vec![Some(true)]);
+    }
+
+    #[test]
+    fn test_named_states() {
+        let mut tm = TuringMachine::with_starting_state(Box::new(Tape::tape(vec![Some('a'), Some('b')])), "scan_right");
+        tm.add_transition(("scan_right", Some('a')), ("scan_right", Some('a'), Direction::Right));
+        tm.add_transition(("scan_right", None), ("q_accept", None, Direction::Hold));
+        tm.add_accepting_state("q_accept");
+
+        let report = tm.run(100, None);
+        assert_eq!(report.outcome, RunOutcome::NoTransition { state: "scan_right", symbol: Some('b'), head_position: 1 });
+        assert_eq!(report.outcome.to_string(), "no transition for state scan_right reading b at position 1");
+
+        assert_eq!(tm.load_input(&[Some('a')]), Ok(()));
+        let report = tm.run(100, None);
+        assert_eq!(report.outcome, RunOutcome::Halted(Verdict::Accept));
+        assert_eq!(report.final_state, "q_accept");
+    }
+
+    #[test]
+    fn test_time_travel() {
+        // Counts upwards in binary, least significant bit first
+        let mut tm = TuringMachine::new(Box::new(Tape::new()));
+        tm.add_transition((0, Some(true)), (0, Some(false), Direction::Right));
+        tm.add_transition((0, Some(false)), (1, Some(true), Direction::Left));
+        tm.add_transition((0, None), (1, Some(true), Direction::Left));
+        tm.add_transition((1, Some(false)), (1, Some(false), Direction::Left));
+        tm.add_transition((1, None), (0, None, Direction::Right));
+        tm.record_history(true);
+
+        let mut snapshots = vec![(tm.tape().contents_trim_blanks(), tm.tape().head_position(), *tm.current_state())];
+        for _ in 0..50 {
+            tm.step();
+            snapshots.push((tm.tape().contents_trim_blanks(), tm.tape().head_position(), *tm.current_state()));
+        }
+        assert_eq!(tm.history().map(|h| h.len()), Some(50));
+
+        assert!(tm.step_back());
+        assert_eq!(tm.steps(), 49);
+        assert_eq!((tm.tape().contents_trim_blanks(), tm.tape().head_position(), *tm.current_state()), snapshots[49]);
+
+        assert!(tm.goto_step(7));
+        assert_eq!((tm.tape().contents_trim_blanks(), tm.tape().head_position(), *tm.current_state()), snapshots[7]);
+
+        // Replaying forward ends up in the same configuration as before
+        assert!(tm.goto_step(50));
+        assert_eq!((tm.tape().contents_trim_blanks(), tm.tape().head_position(), *tm.current_state()), snapshots[50]);
+
+        assert!(tm.goto_step(0));
+        assert!(!tm.step_back());
+
+        // The history of the old tape cannot be undone on a new one
+        tm.step();
+        tm.step();
+        tm.insert_tape(Box::new(Tape::tape(vec![Some(true)])));
+        assert_eq!(tm.steps(), 0);
+        assert!(!tm.step_back());
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        // Bounces between two cells after walking to the end of the input
+        let mut tm = TuringMachine::new(Box::new(Tape::tape(vec![Some(true), Some(true)])));
+        tm.add_transition((0, Some(true)), (0, Some(true), Direction::Right));
+        tm.add_transition((0, None), (1, None, Direction::Right));
+        tm.add_transition((1, None), (0, None, Direction::Left));
+        tm.detect_cycles(true);
+
+        let report = tm.run(1000, None);
+        assert_eq!(report.outcome, RunOutcome::Cycle { start: 2, length: 2 });
+        assert_eq!(report.outcome.to_string(), "provably non-halting: cycle of length 2 starting at step 2");
+
+        // On a blank tape, the cycle starts right away, regardless of the
+        // blanks allocated by moving around
+        let mut tm = TuringMachine::new(Box::new(Tape::<bool>::new()));
+        tm.add_transition((0, None), (1, None, Direction::Right));
+        tm.add_transition((1, None), (0, None, Direction::Left));
+        tm.detect_cycles(true);
+        assert_eq!(tm.run(1000, None).outcome, RunOutcome::Cycle { start: 0, length: 2 });
+
+        // The same holds for a declared blank, which is read from every cell
+        // that has not been written
+        let mut tm = TuringMachine::new(Box::new(Tape::<char>::new()));
+        tm.add_transition((0, Some('_')), (1, Some('_'), Direction::Right));
+        tm.add_transition((1, Some('_')), (0, Some('_'), Direction::Left));
+        tm.set_alphabet(Alphabet::new(['a'], [], Some('_'))).unwrap();
+        tm.detect_cycles(true);
+        assert_eq!(tm.run(1000, None).outcome, RunOutcome::Cycle { start: 0, length: 2 });
+
+        // Walking off to the right never repeats a configuration
+        let mut tm = TuringMachine::new(Box::new(Tape::<bool>::new()));
+        tm.add_transition((0, None), (0, None, Direction::Right));
+        tm.detect_cycles(true);
+        assert_eq!(tm.run(100, None).outcome, RunOutcome::StepLimitReached);
+
+        // Configurations seen after the step travelled back to are forgotten
+        let mut tm = TuringMachine::new(Box::new(Tape::tape(vec![Some(true); 20])));
+        tm.add_transition((0, Some(true)), (0, Some(true), Direction::Right));
+        tm.record_history(true);
+        tm.detect_cycles(true);
+        assert_eq!(tm.run(10, None).outcome, RunOutcome::StepLimitReached);
+        assert!(tm.goto_step(3));
+        assert_eq!(tm.run(10, None).outcome, RunOutcome::StepLimitReached);
+        assert_eq!(tm.run(10, None).outcome, RunOutcome::NoTransition { state: 0, symbol: None, head_position: 20 });
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let mut tm = TuringMachine::new(Box::new(Tape::new()));
+        assert_eq!(tm.set_alphabet(Alphabet::new(['a'], [], Some('_'))), Ok(()));
+        tm.add_transition((0, Some('a')), (0, Some('b'), Direction::Right));
+
+        let err = TapeError::SymbolOutsideAlphabet { symbol: Some('b'), position: 1 };
+        assert_eq!(tm.load_input(&[Some('a'), Some('b')]), Err(err));
+        assert_eq!(tm.load_input(&[Some('a')]), Ok(()));
+
+        // The machine stays as it was, when a transition writes a foreign symbol
+        let err = TapeError::SymbolOutsideAlphabet { symbol: Some('b'), position: 0 };
+        assert_eq!(tm.step(), StepOutcome::TapeError(err));
+        assert_eq!(tm.tape().contents(), vec![Some('a')]);
+        assert_eq!(tm.steps(), 0);
+
+        // A tape that already contains foreign symbols is refused
+        let mut tm = TuringMachine::<char>::new(Box::new(Tape::tape(vec![Some('a'), None, Some('z')])));
+        let err = TapeError::SymbolOutsideAlphabet { symbol: Some('z'), position: 2 };
+        assert_eq!(tm.set_alphabet(Alphabet::new(['a'], [], Some('_'))), Err(err));
+        assert!(tm.alphabet().is_none());
+    }
+
+    #[test]
+    fn test_linear_bounded_tape() {
+        // Looks for a b, but walks past the end of the input when there is none
+        let input = vec![Some('a'), Some('a')];
+        let mut tm = TuringMachine::new(Box::new(Tape::linear_bounded(input, '<', '>', Boundary::Error)));
+        tm.add_transition((0, Some('a')), (0, Some('a'), Direction::Right));
+        tm.add_transition((0, Some('>')), (0, Some('>'), Direction::Right));
+        tm.add_transition((0, Some('b')), (1, Some('b'), Direction::Hold));
+        tm.add_accepting_state(1);
+
+        let report = tm.run(100, None);
+        assert_eq!(report.outcome, RunOutcome::TapeError(TapeError::OutOfBounds { position: 3 }));
+        assert_eq!(report.tape.contents(), vec![Some('<'), Some('a'), Some('a'), Some('>')]);
+        assert_eq!(tm.tape().head_position(), 2);
+
+        assert_eq!(tm.load_input(&[Some('a'), Some('b')]), Ok(()));
+        assert_eq!(tm.run(100, None).outcome, RunOutcome::Halted(Verdict::Accept));
+    }
+}